@@ -1,161 +1,203 @@
-///
-/// This is a pretty horrendous quick and hacky first go and could do with a lot of tidying up.
-use std::{error::Error, fs, path::PathBuf, str::FromStr};
-
-use regex::{Match, Regex};
-
-struct EngineSchematic {
-    schematic_lines: Vec<String>,
-    height: usize,
-    width: usize,
+//! The schematic reader is driven by a set of `SchematicRules`, so that the same code can be
+//! reused for related grid formats with different symbol, gear and adjacency conventions.
+use std::{collections::HashMap, error::Error, fs, path::PathBuf, str::FromStr};
+
+use regex::Regex;
+
+/// Which characters in the schematic count as part symbols. Digits are never symbols.
+#[derive(Debug, Clone)]
+pub enum SymbolClass {
+    /// Any character not in the list is a symbol.
+    AnyExcept(Vec<char>),
+    /// Only characters in the list are symbols.
+    Only(Vec<char>),
 }
 
-impl FromStr for EngineSchematic {
-    type Err = ();
+impl SymbolClass {
+    fn contains(&self, c: char) -> bool {
+        if c.is_ascii_digit() {
+            return false;
+        }
+        match self {
+            SymbolClass::AnyExcept(chars) => !chars.contains(&c),
+            SymbolClass::Only(chars) => chars.contains(&c),
+        }
+    }
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let schematic_lines = s.lines().map(String::from).collect::<Vec<_>>();
+/// Whether adjacency includes diagonal neighbours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    Four,
+    Eight,
+}
 
-        let height = schematic_lines.len();
-        let width = schematic_lines[0].len();
-        Ok(Self {
-            schematic_lines: schematic_lines,
-            height: height,
-            width: width,
-        })
-    }
+/// How the numbers neighbouring a gear are combined into its ratio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GearReduction {
+    Product,
+    Sum,
+    Max,
 }
 
-impl EngineSchematic {
-    fn get_char(&self, i: i32, j: i32) -> char {
-        if i < 0 || i >= self.height as i32 || j < 0 || j >= self.width as i32 {
-            '.'
-        } else {
-            self.schematic_lines[i as usize]
-                .chars()
-                .nth(j as usize)
-                .unwrap()
+impl GearReduction {
+    fn reduce(&self, values: &[i32]) -> i32 {
+        match self {
+            GearReduction::Product => values.iter().product(),
+            GearReduction::Sum => values.iter().sum(),
+            GearReduction::Max => values.iter().copied().max().unwrap_or(0),
         }
     }
+}
 
-    fn get_line(&self, i: i32) -> &str {
-        &self.schematic_lines[i as usize]
-    }
+/// The rules used to interpret a schematic. The default matches the puzzle description.
+#[derive(Debug, Clone)]
+pub struct SchematicRules {
+    pub symbols: SymbolClass,
+    pub gears: Vec<char>,
+    pub gear_neighbours: usize,
+    pub connectivity: Connectivity,
+    pub gear_reduction: GearReduction,
+}
 
-    fn is_part(&self, i: i32, j: i32) -> bool {
-        let c = self.get_char(i, j);
-        (!c.is_digit(10)) && !(c == '.')
+impl Default for SchematicRules {
+    fn default() -> Self {
+        Self {
+            symbols: SymbolClass::AnyExcept(vec!['.']),
+            gears: vec!['*'],
+            gear_neighbours: 2,
+            connectivity: Connectivity::Eight,
+            gear_reduction: GearReduction::Product,
+        }
     }
+}
 
-    fn compute_part_sum(&self) -> i32 {
-        let re = Regex::new(r"\d+").unwrap();
-
-        let mut part_sum = 0;
-
-        for (i, line) in self.schematic_lines.iter().enumerate() {
-            let matches = re.find_iter(line);
-            for m in matches {
-                let start = m.start() as i32;
-                let stop = m.end() as i32;
-
-                let mut part_adjacent = false;
-
-                for offset in [-1, 1] {
-                    let r = i as i32 + offset;
-                    for c in (start - 1)..(stop + 1) {
-                        if self.is_part(r, c) {
-                            part_adjacent = true;
-                        }
-                    }
-                }
+/// A number in the schematic, occupying columns `start..end` of `row`.
+struct PartNumber {
+    row: i32,
+    start: i32,
+    end: i32,
+    value: i32,
+}
 
-                if self.is_part(i as i32, start - 1) || self.is_part(i as i32, stop) {
-                    part_adjacent = true;
-                }
+impl PartNumber {
+    /// All the positions adjacent to the number under the given connectivity.
+    fn neighbours(&self, connectivity: Connectivity) -> Vec<(i32, i32)> {
+        let (first, last) = match connectivity {
+            Connectivity::Four => (self.start, self.end - 1),
+            Connectivity::Eight => (self.start - 1, self.end),
+        };
 
-                if part_adjacent {
-                    part_sum += m.as_str().parse::<i32>().unwrap();
-                }
-            }
+        let mut neighbours = vec![(self.row, self.start - 1), (self.row, self.end)];
+        for offset in [-1, 1] {
+            neighbours.extend((first..=last).map(|c| (self.row + offset, c)));
         }
-        part_sum
+        neighbours
     }
+}
 
-    fn get_gear_neighbours(&self, i: usize, j: usize) -> i32 {
-        let re = Regex::new(r"\d+").unwrap();
+struct EngineSchematic {
+    schematic_lines: Vec<Vec<char>>,
+    numbers: Vec<PartNumber>,
+}
 
-        let mut neighbours: Vec<i32> = vec![];
+impl FromStr for EngineSchematic {
+    type Err = ();
 
-        let i = i as i32;
-        let j = j as i32;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let re = Regex::new(r"[0-9]+").unwrap();
+
+        let numbers = s
+            .lines()
+            .enumerate()
+            .flat_map(|(i, line)| {
+                // Match offsets are in bytes, but the grid is indexed by character, so convert
+                // them in case the line has any non-ASCII symbols.
+                let column = |byte_offset: usize| line[..byte_offset].chars().count() as i32;
+                re.find_iter(line)
+                    .map(|m| PartNumber {
+                        row: i as i32,
+                        start: column(m.start()),
+                        end: column(m.end()),
+                        value: m.as_str().parse::<i32>().unwrap(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let schematic_lines = s.lines().map(|l| l.chars().collect()).collect();
 
-        for offset in [-1, 1] {
-            if self.get_char(i + offset, j).is_digit(10) {
-                let matches = re.find_iter(self.get_line(i + offset));
-                neighbours.push(get_match_over_index(matches, j));
-            } else {
-                if self.get_char(i + offset, j - 1).is_digit(10) {
-                    let matches = re.find_iter(self.get_line(i + offset));
-                    neighbours.push(get_match_over_index(matches, j - 1))
-                }
-                if self.get_char(i + offset, j + 1).is_digit(10) {
-                    let matches = re.find_iter(self.get_line(i + offset));
-                    neighbours.push(get_match_over_index(matches, j + 1));
-                }
-            }
-            if self.get_char(i, j + offset).is_digit(10) {
-                let matches = re.find_iter(self.get_line(i));
-                neighbours.push(get_match_over_index(matches, j + offset))
-            }
-        }
+        Ok(Self {
+            schematic_lines,
+            numbers,
+        })
+    }
+}
 
-        if neighbours.len() == 2 {
-            neighbours[0] * neighbours[1]
-        } else {
-            0
+impl EngineSchematic {
+    fn get_char(&self, i: i32, j: i32) -> char {
+        if i < 0 || j < 0 {
+            return '.';
         }
+        self.schematic_lines
+            .get(i as usize)
+            .and_then(|l| l.get(j as usize))
+            .copied()
+            .unwrap_or('.')
     }
 
-    fn compute_gear_ratio_sum(&self) -> i32 {
-        let mut gear_ratio_sum = 0;
-        for (i, line) in self.schematic_lines.iter().enumerate() {
-            let gears = line
-                .chars()
-                .enumerate()
-                .filter(|(_, c)| c == &'*')
-                .map(|(j, _)| j);
-            for j in gears {
-                gear_ratio_sum += self.get_gear_neighbours(i, j);
+    fn compute_part_sum(&self, rules: &SchematicRules) -> i32 {
+        self.numbers
+            .iter()
+            .filter(|n| {
+                n.neighbours(rules.connectivity)
+                    .iter()
+                    .any(|&(i, j)| rules.symbols.contains(self.get_char(i, j)))
+            })
+            .map(|n| n.value)
+            .sum()
+    }
+
+    fn compute_gear_ratio_sum(&self, rules: &SchematicRules) -> i32 {
+        // Adjacency is symmetric, so collect each gear's neighbours by working outwards from the
+        // numbers instead.
+        let mut gear_neighbours: HashMap<(i32, i32), Vec<i32>> = HashMap::new();
+        for n in &self.numbers {
+            for (i, j) in n.neighbours(rules.connectivity) {
+                if rules.gears.contains(&self.get_char(i, j)) {
+                    gear_neighbours.entry((i, j)).or_default().push(n.value);
+                }
             }
         }
 
-        gear_ratio_sum
+        gear_neighbours
+            .values()
+            .filter(|values| values.len() == rules.gear_neighbours)
+            .map(|values| rules.gear_reduction.reduce(values))
+            .sum()
     }
 }
 
-fn get_match_over_index<'a>(matches: impl Iterator<Item = Match<'a>>, index: i32) -> i32 {
-    let j = index as usize;
-    matches
-        .filter(|m| j >= m.start() && j < m.end())
-        .next()
-        .unwrap()
-        .as_str()
-        .parse::<i32>()
-        .unwrap()
-}
-
-fn compute_answer(puzzle_input: &str, part_two: bool) -> i32 {
+pub fn compute_answer(puzzle_input: &str, part_two: bool, rules: &SchematicRules) -> i32 {
     let engine_schematic = EngineSchematic::from_str(puzzle_input).unwrap();
     if part_two {
-        engine_schematic.compute_gear_ratio_sum()
+        engine_schematic.compute_gear_ratio_sum(rules)
     } else {
-        engine_schematic.compute_part_sum()
+        engine_schematic.compute_part_sum(rules)
     }
 }
 
 pub fn run(input_path: PathBuf, part_two: bool) -> Result<i32, Box<dyn Error>> {
+    run_with_rules(input_path, part_two, &SchematicRules::default())
+}
+
+pub fn run_with_rules(
+    input_path: PathBuf,
+    part_two: bool,
+    rules: &SchematicRules,
+) -> Result<i32, Box<dyn Error>> {
     let puzzle_input = fs::read_to_string(input_path)?;
-    let answer = compute_answer(&puzzle_input, part_two);
+    let answer = compute_answer(&puzzle_input, part_two, rules);
     println!("The answer is {answer}");
     Ok(answer)
 }
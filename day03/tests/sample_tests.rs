@@ -1,5 +1,7 @@
 use std::path::PathBuf;
 
+use day03::{Connectivity, GearReduction, SchematicRules, SymbolClass};
+
 #[test]
 fn test_part_one() {
     let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -15,3 +17,58 @@ fn test_part_two() {
     let answer = day03::run(input_path, true).unwrap();
     assert_eq!(answer, 467835);
 }
+
+#[test]
+fn test_part_one_four_connected() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let rules = SchematicRules {
+        connectivity: Connectivity::Four,
+        ..Default::default()
+    };
+    let answer = day03::run_with_rules(input_path, false, &rules).unwrap();
+    assert_eq!(answer, 2547);
+}
+
+#[test]
+fn test_part_two_gear_sum() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let rules = SchematicRules {
+        gear_reduction: GearReduction::Sum,
+        ..Default::default()
+    };
+    let answer = day03::run_with_rules(input_path, true, &rules).unwrap();
+    assert_eq!(answer, 1855);
+}
+
+#[test]
+fn test_part_one_only_hash_symbols() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let rules = SchematicRules {
+        symbols: SymbolClass::Only(vec!['#']),
+        ..Default::default()
+    };
+    let answer = day03::run_with_rules(input_path, false, &rules).unwrap();
+    assert_eq!(answer, 633);
+}
+
+#[test]
+fn test_non_ascii_symbols() {
+    // The multi-byte symbols must not shift the columns of the numbers after them.
+    let puzzle_input = "\
+é..ü.12
+.34.é..
+...€7..
+";
+    let answer = day03::compute_answer(puzzle_input, false, &SchematicRules::default());
+    assert_eq!(answer, 12 + 34 + 7);
+
+    let rules = SchematicRules {
+        gears: vec!['é'],
+        ..Default::default()
+    };
+    let answer = day03::compute_answer(puzzle_input, true, &rules);
+    assert_eq!(answer, 12 * 7);
+}
@@ -1,4 +1,4 @@
-use std::{collections::HashSet, error::Error, fs, path::PathBuf};
+use std::{collections::HashSet, error::Error, fmt, fs, path::PathBuf};
 
 fn parse_line(line: &str) -> usize {
     let (left, right) = line.split_once(" | ").unwrap();
@@ -18,22 +18,77 @@ fn parse_line(line: &str) -> usize {
     left_set.intersection(&right_set).count()
 }
 
-fn compute_answer(puzzle_input: &str, part_two: bool) -> i32 {
-    let card_matches = puzzle_input.lines().map(|l| parse_line(l));
+/// The outcome of the part two card cascade, tracking where every copy of a card came from.
+/// Cards are indexed from zero, but displayed with their one-based card numbers.
+pub struct CardCascade {
+    /// Total number of copies of each card, including the original.
+    pub copies: Vec<usize>,
+    /// For each card, the earlier cards that won copies of it and how many copies each gave.
+    pub contributions: Vec<Vec<(usize, usize)>>,
+    /// Length of the longest chain of cards winning copies of later cards.
+    pub depth: usize,
+}
 
-    if part_two {
-        let card_values = card_matches.collect::<Vec<_>>();
-        let mut card_counts = vec![1; card_values.len()];
+impl CardCascade {
+    /// Run the cascade over the number of matches on each card.
+    /// Returns an error if a card would win copies of cards past the end of the table.
+    pub fn new(card_matches: &[usize]) -> Result<Self, String> {
+        let card_total = card_matches.len();
+        let mut copies = vec![1; card_total];
+        let mut contributions = vec![vec![]; card_total];
+        let mut depths = vec![0; card_total];
+
+        for (i, &matches) in card_matches.iter().enumerate() {
+            if i + matches >= card_total {
+                return Err(format!(
+                    "Card {} wins copies of {} cards but only {} follow it",
+                    i + 1,
+                    matches,
+                    card_total - i - 1
+                ));
+            }
 
-        for (i, cv) in card_values.iter().enumerate() {
-            for j in (i + 1)..(i + 1 + cv) {
-                card_counts[j] += card_counts[i];
+            for j in (i + 1)..(i + 1 + matches) {
+                copies[j] += copies[i];
+                contributions[j].push((i, copies[i]));
+                depths[j] = depths[j].max(depths[i] + 1);
             }
         }
 
-        card_counts.into_iter().sum()
+        Ok(Self {
+            copies,
+            contributions,
+            depth: depths.into_iter().max().unwrap_or(0),
+        })
+    }
+
+    pub fn total(&self) -> usize {
+        self.copies.iter().sum()
+    }
+}
+
+impl fmt::Display for CardCascade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (copies, contributions)) in self.copies.iter().zip(&self.contributions).enumerate()
+        {
+            write!(f, "Card {}: {} copies (1 original", i + 1, copies)?;
+            for (source, count) in contributions {
+                write!(f, ", {} from card {}", count, source + 1)?;
+            }
+            writeln!(f, ")")?;
+        }
+        write!(f, "Cascade depth: {}", self.depth)
+    }
+}
+
+fn compute_answer(puzzle_input: &str, part_two: bool) -> Result<i32, String> {
+    let card_matches = puzzle_input.lines().map(|l| parse_line(l));
+
+    if part_two {
+        let card_values = card_matches.collect::<Vec<_>>();
+        Ok(CardCascade::new(&card_values)?.total() as i32)
     } else {
-        card_matches
+        Ok(card_matches
             .map(|c| {
                 if c == 0 {
                     0
@@ -41,13 +96,22 @@ fn compute_answer(puzzle_input: &str, part_two: bool) -> i32 {
                     i32::pow(2, (c - 1) as u32)
                 }
             })
-            .sum()
+            .sum())
     }
 }
 
 pub fn run(input_path: PathBuf, part_two: bool) -> Result<i32, Box<dyn Error>> {
     let puzzle_input = fs::read_to_string(input_path)?;
-    let answer = compute_answer(&puzzle_input, part_two);
+    let answer = compute_answer(&puzzle_input, part_two)?;
     println!("The answer is {answer}");
     Ok(answer)
 }
+
+/// Run the part two cascade and print where every card's copies came from.
+pub fn run_report(input_path: PathBuf) -> Result<CardCascade, Box<dyn Error>> {
+    let puzzle_input = fs::read_to_string(input_path)?;
+    let card_matches = puzzle_input.lines().map(parse_line).collect::<Vec<_>>();
+    let cascade = CardCascade::new(&card_matches)?;
+    println!("{cascade}");
+    Ok(cascade)
+}
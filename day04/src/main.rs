@@ -1,13 +1,29 @@
 use clap::Parser;
 use std::process;
 
-use day04::run;
+use day04::{run, run_report};
 use util::Cli;
 
+#[derive(Parser)]
+struct Day04Cli {
+    #[command(flatten)]
+    cli: Cli,
+
+    /// Report how many copies of each card were won, and from which cards.
+    #[arg(long)]
+    report: bool,
+}
+
 fn main() {
-    let cli = Cli::parse();
+    let cli = Day04Cli::parse();
+
+    let result = if cli.report {
+        run_report(cli.cli.puzzle_input_path).map(|_| ())
+    } else {
+        run(cli.cli.puzzle_input_path, cli.cli.part_two).map(|_| ())
+    };
 
-    if let Err(e) = run(cli.puzzle_input_path, cli.part_two) {
+    if let Err(e) = result {
         eprintln!("Error: {e}");
         process::exit(1);
     }
@@ -15,3 +15,20 @@ fn test_part_two() {
     let answer = day04::run(input_path, true).unwrap();
     assert_eq!(answer, 30);
 }
+
+#[test]
+fn test_report() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let cascade = day04::run_report(input_path).unwrap();
+    assert_eq!(cascade.copies, vec![1, 2, 4, 8, 14, 1]);
+    assert_eq!(cascade.contributions[3], vec![(0, 1), (1, 2), (2, 4)]);
+    assert_eq!(cascade.depth, 4);
+}
+
+#[test]
+fn test_part_two_malformed() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/malformed_input.txt");
+    assert!(day04::run(input_path, true).is_err());
+}
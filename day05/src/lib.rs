@@ -1,154 +1,347 @@
-use std::{error::Error, fs, path::PathBuf, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt, fs,
+    path::PathBuf,
+    str::FromStr,
+};
 
 /// Represents seeds covering a range.
 /// Range is half-inclusive.
-struct SeedRange {
-    start: i64,
-    end: i64,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeedRange {
+    pub start: i64,
+    pub end: i64,
 }
+
+/// Sort a set of ranges and merge any that overlap or touch.
+fn merge_ranges(mut ranges: Vec<SeedRange>) -> Vec<SeedRange> {
+    ranges.sort_by_key(|r| r.start);
+
+    let mut merged: Vec<SeedRange> = vec![];
+    for r in ranges.into_iter().filter(|r| r.start < r.end) {
+        match merged.last_mut() {
+            Some(last) if r.start <= last.end => last.end = last.end.max(r.end),
+            _ => merged.push(r),
+        }
+    }
+    merged
+}
+
 /// Represents a mapping from a particular source range to a destination range.
 /// Ranges are half-inclusive.
 struct MapRange {
     src_start: i64,
     src_end: i64,
     dst_start: i64,
-    dst_end: i64,
 }
 
 impl FromStr for MapRange {
-    type Err = ();
+    type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let values = s
             .split(" ")
-            .map(|s| s.parse::<i64>().unwrap())
-            .collect::<Vec<_>>();
+            .map(|v| {
+                v.parse::<i64>()
+                    .map_err(|e| format!("Invalid map line {s:?}: {e}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
-        let d_start = values[0];
-        let s_start = values[1];
-        let length = values[2];
+        let [d_start, s_start, length] = values[..] else {
+            return Err(format!("Map line {s:?} should have three values"));
+        };
 
         Ok(Self {
             src_start: s_start,
             src_end: s_start + length,
             dst_start: d_start,
-            dst_end: d_start + length,
         })
     }
 }
 
-/// Collection of MapRanges.
+/// Collection of MapRanges converting from one category to another.
 struct SeedMap {
+    source: String,
+    destination: String,
     map_ranges: Vec<MapRange>,
 }
 
 impl FromStr for SeedMap {
-    type Err = ();
+    type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut ranges = s
-            .split(":\n")
-            .last()
-            .unwrap()
+        let (header, body) = s
+            .split_once(":\n")
+            .ok_or_else(|| format!("Missing map header in block {s:?}"))?;
+
+        let (source, destination) = header
+            .strip_suffix(" map")
+            .and_then(|h| h.split_once("-to-"))
+            .ok_or_else(|| format!("Invalid map header {header:?}"))?;
+
+        let mut ranges = body
             .lines()
-            .map(|s| MapRange::from_str(s).unwrap())
-            .collect::<Vec<_>>();
+            .map(MapRange::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
         ranges.sort_by_key(|r| r.src_start);
+        if let Some(w) = ranges.windows(2).find(|w| w[1].src_start < w[0].src_end) {
+            return Err(format!(
+                "Source ranges starting at {} and {} overlap in the {header} map",
+                w[0].src_start, w[1].src_start
+            ));
+        }
 
-        Ok(Self { map_ranges: ranges })
+        Ok(Self {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            map_ranges: ranges,
+        })
     }
 }
 
 impl SeedMap {
-    /// Apply transformation to a single seed number.
-    fn map_seed(&self, seed: i64) -> i64 {
-        let map_range = self
-            .map_ranges
-            .iter()
-            .filter(|r| seed >= r.src_start && seed < r.src_end)
-            .next();
+    fn to_piecewise(&self) -> PiecewiseMap {
+        PiecewiseMap::new(
+            self.map_ranges
+                .iter()
+                .map(|m| Piece {
+                    start: m.src_start,
+                    end: m.src_end,
+                    offset: m.dst_start - m.src_start,
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Inputs in `start..end` are shifted by `offset`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Piece {
+    start: i64,
+    end: i64,
+    offset: i64,
+}
+
+/// A map made of pieces that each shift a range of inputs by a constant offset.
+/// The pieces are kept sorted and cover every non-negative input, with adjacent pieces that share
+/// an offset merged together, so two maps with the same behaviour compare equal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiecewiseMap {
+    pieces: Vec<Piece>,
+}
+
+impl PiecewiseMap {
+    /// Build a normalised map from non-overlapping pieces, treating any gaps as identity.
+    fn new(mut pieces: Vec<Piece>) -> Self {
+        pieces.sort_by_key(|p| p.start);
 
-        match map_range {
-            Some(m) => seed - m.src_start + m.dst_start,
-            None => seed,
+        let mut covered = vec![];
+        let mut next_start = 0;
+        for p in pieces {
+            if p.start > next_start {
+                covered.push(Piece {
+                    start: next_start,
+                    end: p.start,
+                    offset: 0,
+                });
+            }
+            next_start = p.end;
+            covered.push(p);
+        }
+        covered.push(Piece {
+            start: next_start,
+            end: i64::MAX,
+            offset: 0,
+        });
+
+        let mut normalised: Vec<Piece> = vec![];
+        for p in covered.into_iter().filter(|p| p.start < p.end) {
+            match normalised.last_mut() {
+                Some(last) if last.offset == p.offset && last.end == p.start => last.end = p.end,
+                _ => normalised.push(p),
+            }
         }
+
+        Self { pieces: normalised }
     }
 
-    /// Apply transformation to a range of seed numbers.
-    /// As the map ranges may overlap the seed ranges, multiple seed ranges can be returned.
-    fn map_seed_range(&self, src_range: SeedRange) -> Vec<SeedRange> {
-        let mut dst_ranges: Vec<SeedRange> = vec![];
-
-        for m in &self.map_ranges {
-            // map_ranges is sorted
-            if src_range.start < m.src_start && src_range.end < m.src_start {
-                // The src_range is entirely to the left of the map_range, so no transform needed.
-                dst_ranges.push(src_range);
-                return dst_ranges;
-            } else if src_range.start < m.src_start && src_range.end >= m.src_start {
-                // The src_range overlaps the map_range to the left, so split the src_range at the
-                // left intersection.
-                dst_ranges.push(SeedRange {
-                    start: src_range.start,
-                    end: m.src_start - 1,
-                });
-                dst_ranges.append(&mut self.map_seed_range(SeedRange {
-                    start: m.src_start,
-                    end: src_range.end,
-                }));
-                return dst_ranges;
-            } else if src_range.start >= m.src_start && src_range.end < m.src_end {
-                // The src_range is entirely contained by the map_range, so transform it.
-                dst_ranges.push(SeedRange {
-                    start: src_range.start - m.src_start + m.dst_start,
-                    end: src_range.end - m.src_start + m.dst_start,
-                });
-                return dst_ranges;
-            } else if src_range.start < m.src_end && src_range.end >= m.src_end {
-                // The src range overlaps the map_range to the right, so split it at the right
-                // intersection and transform the left split.
-                dst_ranges.push(SeedRange {
-                    start: src_range.start - m.src_start + m.dst_start,
-                    end: m.dst_end,
+    pub fn identity() -> Self {
+        Self::new(vec![])
+    }
+
+    /// Map a single input.
+    pub fn apply(&self, input: i64) -> i64 {
+        let i = self.pieces.partition_point(|p| p.end <= input);
+        input + self.pieces[i].offset
+    }
+
+    /// Compose two maps into one which applies this map followed by `next`.
+    pub fn then(&self, next: &PiecewiseMap) -> PiecewiseMap {
+        let mut pieces = vec![];
+
+        for p in &self.pieces {
+            // Split the image of this piece wherever it crosses a boundary of the next map.
+            let (lo, hi) = (p.start + p.offset, p.end.saturating_add(p.offset));
+            let first = next.pieces.partition_point(|q| q.end <= lo);
+            for q in next.pieces[first..].iter().take_while(|q| q.start < hi) {
+                pieces.push(Piece {
+                    start: lo.max(q.start) - p.offset,
+                    end: hi.min(q.end).saturating_sub(p.offset),
+                    offset: p.offset + q.offset,
                 });
-                dst_ranges.append(&mut self.map_seed_range(SeedRange {
-                    start: m.src_end,
-                    end: src_range.end,
-                }));
-                return dst_ranges;
             }
         }
 
-        // If we got here the src_range is to the right of all the map ranges.
-        dst_ranges.push(src_range);
-        dst_ranges
+        Self::new(pieces)
     }
 
-    /// Apply transformation to a set of seed ranges and concatenate the results together.
-    fn map_seed_ranges(&self, seed_ranges: Vec<SeedRange>) -> Vec<SeedRange> {
-        seed_ranges
-            .into_iter()
-            .flat_map(|s| self.map_seed_range(s))
-            .collect::<Vec<_>>()
+    /// Find every input range that maps into one of the given output ranges.
+    pub fn preimage(&self, outputs: &[SeedRange]) -> Vec<SeedRange> {
+        let mut inputs = vec![];
+
+        for p in &self.pieces {
+            let (lo, hi) = (p.start + p.offset, p.end.saturating_add(p.offset));
+            for r in outputs {
+                let (start, end) = (lo.max(r.start), hi.min(r.end));
+                if start < end {
+                    inputs.push(SeedRange {
+                        start: start - p.offset,
+                        end: end.saturating_sub(p.offset),
+                    });
+                }
+            }
+        }
+
+        merge_ranges(inputs)
+    }
+
+    /// Find the smallest output of the map over a set of input ranges.
+    pub fn min_output(&self, inputs: &[SeedRange]) -> Option<i64> {
+        inputs
+            .iter()
+            .flat_map(|r| {
+                let first = self.pieces.partition_point(|p| p.end <= r.start);
+                self.pieces[first..]
+                    .iter()
+                    .take_while(move |p| p.start < r.end)
+                    .map(move |p| r.start.max(p.start) + p.offset)
+            })
+            .min()
     }
 }
 
-pub fn compute_answer(puzzle_input: &str, part_two: bool) -> i64 {
-    let (seed_list, map_list) = puzzle_input.split_once("\n\n").unwrap();
+impl fmt::Display for PiecewiseMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for p in &self.pieces {
+            if p.end == i64::MAX {
+                writeln!(
+                    f,
+                    "{}.. => {}.. ({:+})",
+                    p.start,
+                    p.start + p.offset,
+                    p.offset
+                )?;
+            } else {
+                writeln!(
+                    f,
+                    "{}..{} => {}..{} ({:+})",
+                    p.start,
+                    p.end,
+                    p.start + p.offset,
+                    p.end + p.offset,
+                    p.offset
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The seed list along with every map in the almanac, keyed by the category they convert from.
+pub struct Almanac {
+    seeds: Vec<i64>,
+    maps: HashMap<String, SeedMap>,
+}
+
+impl FromStr for Almanac {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (seed_list, map_list) = s.split_once("\n\n").ok_or("Almanac has no maps")?;
 
-    let maps = map_list
-        .split("\n\n")
-        .map(|s| SeedMap::from_str(s).unwrap())
-        .collect::<Vec<_>>();
+        let seeds = seed_list
+            .strip_prefix("seeds: ")
+            .ok_or("Almanac has no seed list")?
+            .split(" ")
+            .map(|s| s.parse::<i64>().map_err(|e| e.to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
 
-    let seed_vec = seed_list[7..]
-        .split(" ")
-        .map(|s| s.parse::<i64>().unwrap())
-        .collect::<Vec<_>>();
+        let mut maps = HashMap::new();
+        for block in map_list.split("\n\n") {
+            let map = SeedMap::from_str(block.trim())?;
+            match maps.get(&map.source) {
+                Some(SeedMap { destination, .. }) if *destination == map.destination => {
+                    return Err(format!(
+                        "Map {}-to-{} appears more than once",
+                        map.source, map.destination
+                    ));
+                }
+                Some(_) => {
+                    return Err(format!(
+                        "Category {:?} is mapped more than once",
+                        map.source
+                    ));
+                }
+                None => {}
+            }
+            maps.insert(map.source.clone(), map);
+        }
 
-    if part_two {
+        Ok(Self { seeds, maps })
+    }
+}
+
+impl Almanac {
+    /// Follow the chain of maps from one category to another, composing them into a single map.
+    pub fn conversion(&self, from: &str, to: &str) -> Result<PiecewiseMap, String> {
+        let mut composed = PiecewiseMap::identity();
+        let mut visited = HashSet::new();
+        let mut category = from;
+
+        while category != to {
+            if !visited.insert(category) {
+                return Err(format!(
+                    "Categories from {from:?} form a cycle at {category:?}"
+                ));
+            }
+            let map = self
+                .maps
+                .get(category)
+                .ok_or_else(|| format!("Chain from {from:?} to {to:?} breaks at {category:?}"))?;
+            composed = composed.then(&map.to_piecewise());
+            category = &map.destination;
+        }
+
+        Ok(composed)
+    }
+}
+
+/// Convert the almanac's seed list, treated as values of the `from` category, into the `to`
+/// category and find the lowest result.
+pub fn compute_answer(
+    puzzle_input: &str,
+    part_two: bool,
+    from: &str,
+    to: &str,
+) -> Result<i64, String> {
+    let almanac = Almanac::from_str(puzzle_input)?;
+    let map = almanac.conversion(from, to)?;
+
+    let answer = if part_two {
         // Pair up the seed inputs to get the ranges.
-        let seed_ranges = seed_vec
+        let seed_ranges = almanac
+            .seeds
             .chunks(2)
             .map(|c| SeedRange {
                 start: c[0],
@@ -156,24 +349,38 @@ pub fn compute_answer(puzzle_input: &str, part_two: bool) -> i64 {
             })
             .collect::<Vec<_>>();
 
-        maps.iter()
-            .fold(seed_ranges, |s, m| m.map_seed_ranges(s))
-            .iter()
-            .map(|s| s.start)
-            .min()
-            .unwrap()
+        map.min_output(&seed_ranges)
     } else {
-        seed_vec
-            .into_iter()
-            .map(|seed| maps.iter().fold(seed, |s, m| m.map_seed(s)))
-            .min()
-            .unwrap()
-    }
+        almanac.seeds.iter().map(|&seed| map.apply(seed)).min()
+    };
+
+    answer.ok_or_else(|| "Almanac has an empty seed list".to_string())
 }
 
 pub fn run(input_path: PathBuf, part_two: bool) -> Result<i64, Box<dyn Error>> {
+    run_conversion(input_path, part_two, "seed", "location")
+}
+
+pub fn run_conversion(
+    input_path: PathBuf,
+    part_two: bool,
+    from: &str,
+    to: &str,
+) -> Result<i64, Box<dyn Error>> {
     let puzzle_input = fs::read_to_string(input_path)?;
-    let answer = compute_answer(&puzzle_input, part_two);
+    let answer = compute_answer(&puzzle_input, part_two, from, to)?;
     println!("The answer is {answer}");
     Ok(answer)
 }
+
+/// Compose the maps between two categories into a single map and print it.
+pub fn run_compose(
+    input_path: PathBuf,
+    from: &str,
+    to: &str,
+) -> Result<PiecewiseMap, Box<dyn Error>> {
+    let puzzle_input = fs::read_to_string(input_path)?;
+    let map = Almanac::from_str(&puzzle_input)?.conversion(from, to)?;
+    print!("{map}");
+    Ok(map)
+}
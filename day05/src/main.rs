@@ -1,13 +1,43 @@
 use clap::Parser;
 use std::process;
 
-use day05::run;
+use day05::{run_compose, run_conversion};
 use util::Cli;
 
+#[derive(Parser)]
+struct Day05Cli {
+    #[command(flatten)]
+    cli: Cli,
+
+    /// Print the maps between the two categories composed into a single map.
+    #[arg(long)]
+    compose: bool,
+
+    /// Category to convert from. The seed list is read as values of this category.
+    #[arg(long, default_value = "seed")]
+    from: String,
+
+    /// Category to convert to.
+    #[arg(long, default_value = "location")]
+    to: String,
+}
+
 fn main() {
-    let cli = Cli::parse();
+    let cli = Day05Cli::parse();
+
+    let result = if cli.compose {
+        run_compose(cli.cli.puzzle_input_path, &cli.from, &cli.to).map(|_| ())
+    } else {
+        run_conversion(
+            cli.cli.puzzle_input_path,
+            cli.cli.part_two,
+            &cli.from,
+            &cli.to,
+        )
+        .map(|_| ())
+    };
 
-    if let Err(e) = run(cli.puzzle_input_path, cli.part_two) {
+    if let Err(e) = result {
         eprintln!("Error: {e}");
         process::exit(1);
     }
@@ -1,5 +1,7 @@
 use std::path::PathBuf;
 
+use day05::SeedRange;
+
 #[test]
 fn test_part_one() {
     let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -15,3 +17,93 @@ fn test_part_two() {
     let answer = day05::run(input_path, true).unwrap();
     assert_eq!(answer, 46);
 }
+
+#[test]
+fn test_composed_map() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let map = day05::run_compose(input_path, "seed", "location").unwrap();
+
+    let locations = [79, 14, 55, 13].map(|seed| map.apply(seed));
+    assert_eq!(locations, [82, 43, 86, 35]);
+
+    let seeds = map.preimage(&[SeedRange { start: 46, end: 47 }]);
+    assert_eq!(seeds, vec![SeedRange { start: 82, end: 83 }]);
+
+    let seed_ranges = [
+        SeedRange { start: 79, end: 93 },
+        SeedRange { start: 55, end: 68 },
+    ];
+    assert_eq!(map.min_output(&seed_ranges), Some(46));
+}
+
+#[test]
+fn test_part_two_shuffled_maps() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/shuffled_input.txt");
+    let answer = day05::run(input_path, true).unwrap();
+    assert_eq!(answer, 46);
+}
+
+#[test]
+fn test_named_conversion() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let map = day05::run_compose(input_path, "soil", "humidity").unwrap();
+    assert_eq!(map.apply(81), 78);
+}
+
+#[test]
+fn test_broken_chain() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/broken_chain_input.txt");
+    assert!(day05::run(input_path, false).is_err());
+}
+
+#[test]
+fn test_duplicate_map() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/duplicate_map_input.txt");
+    assert!(day05::run(input_path, false).is_err());
+}
+
+#[test]
+fn test_invalid_maps() {
+    let duplicate_header = "\
+seeds: 79 14
+
+seed-to-soil map:
+50 98 2
+
+seed-to-soil map:
+52 50 48
+";
+    let err = day05::compute_answer(duplicate_header, false, "seed", "soil").unwrap_err();
+    assert!(err.contains("seed-to-soil appears more than once"), "{err}");
+
+    let overlapping = "\
+seeds: 79 14
+
+seed-to-soil map:
+50 98 2
+52 50 49
+";
+    let err = day05::compute_answer(overlapping, false, "seed", "soil").unwrap_err();
+    assert!(err.contains("overlap"), "{err}");
+
+    let malformed = "\
+seeds: 79 14
+
+seed-to-soil map:
+50 98
+";
+    assert!(day05::compute_answer(malformed, false, "seed", "soil").is_err());
+
+    let malformed = "\
+seeds: 79 14
+
+seed-to-soil map:
+50 x 2
+";
+    assert!(day05::compute_answer(malformed, false, "seed", "soil").is_err());
+}
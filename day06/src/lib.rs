@@ -1,44 +1,449 @@
-use std::{error::Error, fs, iter::zip, path::PathBuf};
+use std::{error::Error, fs, iter::zip, ops::RangeInclusive, path::PathBuf, str::FromStr};
 
-fn count_ways(time: i64, distance: i64) -> i64 {
-    // Solve the quadratic equation
-    let a = -1.0;
-    let b = time as f64;
-    let c = -(distance) as f64;
+use num::{BigInt, Zero};
 
-    let sqrt = (b.powi(2) - 4.0 * a * c).sqrt();
-    let root_1 = (-b - sqrt) / (2.0 * a);
-    let root_2 = (-b + sqrt) / (2.0 * a);
+/// One phase of charging the boat while the button is held, gaining `rate` mm/ms of speed for
+/// every ms held. A phase without a duration lasts until the button is released.
+#[derive(Debug, Clone, Copy)]
+pub struct ChargePhase {
+    pub rate: u128,
+    pub duration: Option<u128>,
+}
+
+impl FromStr for ChargePhase {
+    type Err = String;
+
+    /// Parse a phase written as `rate:duration`, or just `rate` for a phase that lasts until the
+    /// button is released.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |v: &str| {
+            v.parse::<u128>()
+                .map_err(|e| format!("Invalid charge phase {s:?}: {e}"))
+        };
+        match s.split_once(':') {
+            Some((rate, duration)) => Ok(Self {
+                rate: parse(rate)?,
+                duration: Some(parse(duration)?),
+            }),
+            None => Ok(Self {
+                rate: parse(s)?,
+                duration: None,
+            }),
+        }
+    }
+}
+
+/// How the boat's speed depends on how long the button is held. Once released the boat coasts at
+/// that speed for the rest of the race.
+pub enum RaceModel {
+    /// Charge through each phase in turn, never exceeding the speed cap. Once every phase is over
+    /// holding the button only loses time. The winning hold times are solved analytically.
+    Charged {
+        phases: Vec<ChargePhase>,
+        speed_cap: Option<u128>,
+    },
+    /// An arbitrary speed for each hold time. The winning hold times are found numerically by
+    /// checking every hold time.
+    Custom(Box<dyn Fn(u128) -> u128>),
+}
 
-    // Required to ensure we get the values of t that beat the distance.
-    (root_1.ceil() - root_2.floor() - 1.0) as i64
+/// A run of hold times `start..=end` over which speed increases linearly,
+/// as `speed + rate * (t - start)`.
+struct Segment {
+    start: u128,
+    end: u128,
+    speed: u128,
+    rate: u128,
 }
 
-fn compute_answer(puzzle_input: &str, part_two: bool) -> i64 {
+impl RaceModel {
+    /// The boat from the puzzle, gaining 1 mm/ms of speed for every ms held.
+    pub fn standard() -> Self {
+        Self::constant(1, None)
+    }
+
+    pub fn constant(rate: u128, speed_cap: Option<u128>) -> Self {
+        RaceModel::Charged {
+            phases: vec![ChargePhase {
+                rate,
+                duration: None,
+            }],
+            speed_cap,
+        }
+    }
+
+    /// Speed of the boat after holding the button for `hold` ms.
+    pub fn speed(&self, hold: u128) -> u128 {
+        match self {
+            RaceModel::Charged { .. } => self
+                .segments(hold)
+                .last()
+                .map_or(0, |s| s.speed + s.rate * (hold - s.start)),
+            RaceModel::Custom(speed) => speed(hold),
+        }
+    }
+
+    /// Split hold times `0..=time` into segments over which speed increases linearly.
+    fn segments(&self, time: u128) -> Vec<Segment> {
+        let RaceModel::Charged { phases, speed_cap } = self else {
+            return vec![];
+        };
+        let cap = speed_cap.unwrap_or(u128::MAX);
+
+        let mut segments = vec![];
+        let mut start = 0;
+        let mut speed = 0;
+        for phase in phases {
+            if start > time {
+                break;
+            }
+            if phase.duration == Some(0) {
+                continue;
+            }
+            let end = phase.duration.map_or(time, |d| {
+                start.saturating_add(d).saturating_sub(1).min(time)
+            });
+
+            // Stop accelerating partway through the phase if the boat reaches the speed cap. Without
+            // a cap that can be beyond the longest possible race, in which case it never happens.
+            let capped_at = match phase.rate {
+                0 => end,
+                rate => start
+                    .checked_add(cap.saturating_sub(speed) / rate)
+                    .unwrap_or(end),
+            };
+            if capped_at < end {
+                segments.push(Segment {
+                    start,
+                    end: capped_at,
+                    speed,
+                    rate: phase.rate,
+                });
+                segments.push(Segment {
+                    start: capped_at + 1,
+                    end,
+                    speed: cap,
+                    rate: 0,
+                });
+                speed = cap;
+            } else {
+                segments.push(Segment {
+                    start,
+                    end,
+                    speed,
+                    rate: phase.rate,
+                });
+                // The phase can end with the boat going faster than the cap, so clamp it before
+                // the next phase starts.
+                let gained = phase.rate.saturating_mul((end - start).saturating_add(1));
+                speed = speed.saturating_add(gained).min(cap);
+            }
+            // Stop if the phase runs right up to the longest possible race.
+            let Some(next_start) = end.checked_add(1) else {
+                return segments;
+            };
+            start = next_start;
+        }
+
+        if start <= time {
+            segments.push(Segment {
+                start,
+                end: time,
+                speed: speed.min(cap),
+                rate: 0,
+            });
+        }
+        segments
+    }
+
+    /// Find every range of hold times that travels further than `distance` in `time` ms.
+    pub fn winning_intervals(
+        &self,
+        time: u128,
+        distance: u128,
+    ) -> Result<Vec<RangeInclusive<u128>>, String> {
+        let mut intervals = vec![];
+        match self {
+            RaceModel::Charged { .. } => intervals.extend(
+                self.segments(time)
+                    .iter()
+                    .filter_map(|s| winning_in_segment(s, time, distance)),
+            ),
+            RaceModel::Custom(speed) => {
+                for t in 0..=time {
+                    let travelled = speed(t).checked_mul(time - t).ok_or_else(|| {
+                        format!("Distance travelled holding for {t} ms overflows")
+                    })?;
+                    if travelled > distance {
+                        intervals.push(t..=t);
+                    }
+                }
+            }
+        }
+
+        // Join up intervals from neighbouring segments.
+        let mut merged: Vec<RangeInclusive<u128>> = vec![];
+        for i in intervals {
+            match merged.last_mut() {
+                Some(last) if *last.end() + 1 == *i.start() => *last = *last.start()..=*i.end(),
+                _ => merged.push(i),
+            }
+        }
+        Ok(merged)
+    }
+
+    pub fn count_ways(&self, time: u128, distance: u128) -> Result<u128, String> {
+        Ok(self
+            .winning_intervals(time, distance)?
+            .iter()
+            .map(|i| i.end() - i.start() + 1)
+            .sum())
+    }
+}
+
+/// Find the hold times in a segment that travel further than `distance`, using only integer
+/// arithmetic. The distances involve the square of the race time, so they are worked out as
+/// `BigInt`s to handle any `u128` race.
+fn winning_in_segment(
+    segment: &Segment,
+    time: u128,
+    distance: u128,
+) -> Option<RangeInclusive<u128>> {
+    let (t, d) = (BigInt::from(time), BigInt::from(distance));
+    let r = BigInt::from(segment.rate);
+    // Speed at hold time h is c + r * h.
+    let c = BigInt::from(segment.speed) - &r * segment.start;
+    let travelled = |h: &BigInt| (&c + &r * h) * (&t - h);
+
+    let (lowest, highest) = if r.is_zero() {
+        // Constant speed, so every hold time up to a cut-off wins.
+        if c.is_zero() {
+            return None;
+        }
+        (BigInt::from(segment.start), &t - &d / &c - 1)
+    } else {
+        // The winning hold times lie strictly between the roots of
+        // -r * h^2 + (r * t - c) * h + c * t - d = 0. Start from the integer square root of the
+        // discriminant and step each end into place to correct for rounding.
+        let discriminant: BigInt = (&r * &t + &c).pow(2u32) - 4 * &r * &d;
+        if discriminant <= BigInt::zero() {
+            return None;
+        }
+        let root = discriminant.sqrt();
+
+        let mut lowest = (&r * &t - &c - &root) / (2 * &r);
+        let mut highest = (&r * &t - &c + &root) / (2 * &r);
+        while lowest <= highest && travelled(&lowest) <= d {
+            lowest += 1;
+        }
+        while travelled(&(&lowest - 1)) > d {
+            lowest -= 1;
+        }
+        while highest >= lowest && travelled(&highest) <= d {
+            highest -= 1;
+        }
+        while travelled(&(&highest + 1)) > d {
+            highest += 1;
+        }
+        (lowest, highest)
+    };
+
+    let lowest = lowest.max(BigInt::from(segment.start));
+    let highest = highest.min(BigInt::from(segment.end));
+    // Both ends are now within the segment, so they fit back into a u128.
+    (lowest <= highest)
+        .then(|| u128::try_from(&lowest).unwrap()..=u128::try_from(&highest).unwrap())
+}
+
+fn compute_answer(puzzle_input: &str, part_two: bool, model: &RaceModel) -> Result<u128, String> {
     let (time_str, distance_str) = puzzle_input.split_once("\n").unwrap();
 
     if part_two {
-        let time = time_str[5..].replace(" ", "").parse::<i64>().unwrap();
-        let distance = distance_str[9..].replace(" ", "").parse::<i64>().unwrap();
-        count_ways(time, distance)
+        let time = time_str[5..].replace(" ", "").parse::<u128>().unwrap();
+        let distance = distance_str[9..]
+            .trim()
+            .replace(" ", "")
+            .parse::<u128>()
+            .unwrap();
+        model.count_ways(time, distance)
     } else {
         let times = time_str[5..]
             .split_whitespace()
-            .map(|s| s.parse::<i64>().unwrap());
+            .map(|s| s.parse::<u128>().unwrap());
 
         let distances = distance_str[9..]
             .split_whitespace()
-            .map(|s| s.parse::<i64>().unwrap());
+            .map(|s| s.parse::<u128>().unwrap());
 
         zip(times, distances)
-            .map(|(t, d)| count_ways(t, d))
-            .product()
+            .map(|(t, d)| model.count_ways(t, d))
+            .product::<Result<_, _>>()
     }
 }
 
-pub fn run(input_path: PathBuf, part_two: bool) -> Result<i64, Box<dyn Error>> {
+pub fn run(input_path: PathBuf, part_two: bool) -> Result<u128, Box<dyn Error>> {
+    run_with_model(input_path, part_two, &RaceModel::standard())
+}
+
+pub fn run_with_model(
+    input_path: PathBuf,
+    part_two: bool,
+    model: &RaceModel,
+) -> Result<u128, Box<dyn Error>> {
     let puzzle_input = fs::read_to_string(input_path)?;
-    let answer = compute_answer(&puzzle_input, part_two);
+    let answer = compute_answer(&puzzle_input, part_two, model)?;
     println!("The answer is {answer}");
     Ok(answer)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count_ways(time: u128, distance: u128) -> u128 {
+        RaceModel::standard().count_ways(time, distance).unwrap()
+    }
+
+    fn brute_force(time: u128, distance: u128) -> u128 {
+        (0..=time).filter(|t| t * (time - t) > distance).count() as u128
+    }
+
+    #[test]
+    fn test_count_ways_exact_root() {
+        // Hold times 2 and 8 travel exactly 16mm, so only 3..=7 win.
+        assert_eq!(count_ways(10, 16), 5);
+        assert_eq!(count_ways(30, 200), 9);
+    }
+
+    #[test]
+    fn test_count_ways_brute_force() {
+        for time in 0..60 {
+            for distance in 0..(time * time / 4 + 2) {
+                assert_eq!(count_ways(time, distance), brute_force(time, distance));
+            }
+        }
+    }
+
+    fn check_model_against_custom(model: RaceModel) {
+        let speeds = (0..=40).map(|t| model.speed(t)).collect::<Vec<_>>();
+        let custom = RaceModel::Custom(Box::new(move |t| speeds[t as usize]));
+        for distance in 0..200 {
+            assert_eq!(
+                model.winning_intervals(40, distance).unwrap(),
+                custom.winning_intervals(40, distance).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_model_speed() {
+        let model = RaceModel::Charged {
+            phases: vec![
+                ChargePhase {
+                    rate: 3,
+                    duration: Some(2),
+                },
+                ChargePhase {
+                    rate: 1,
+                    duration: Some(4),
+                },
+            ],
+            speed_cap: Some(9),
+        };
+        let speeds = (0..10).map(|t| model.speed(t)).collect::<Vec<_>>();
+        assert_eq!(speeds, vec![0, 3, 6, 7, 8, 9, 9, 9, 9, 9]);
+    }
+
+    #[test]
+    fn test_constant_acceleration() {
+        check_model_against_custom(RaceModel::constant(3, None));
+    }
+
+    #[test]
+    fn test_speed_cap() {
+        check_model_against_custom(RaceModel::constant(2, Some(7)));
+    }
+
+    #[test]
+    fn test_charge_then_coast() {
+        check_model_against_custom(RaceModel::Charged {
+            phases: vec![
+                ChargePhase {
+                    rate: 3,
+                    duration: Some(5),
+                },
+                ChargePhase {
+                    rate: 0,
+                    duration: Some(3),
+                },
+                ChargePhase {
+                    rate: 1,
+                    duration: Some(10),
+                },
+            ],
+            speed_cap: Some(20),
+        });
+    }
+
+    #[test]
+    fn test_coast_then_charge() {
+        check_model_against_custom(RaceModel::Charged {
+            phases: vec![
+                ChargePhase {
+                    rate: 0,
+                    duration: Some(2),
+                },
+                ChargePhase {
+                    rate: 1,
+                    duration: None,
+                },
+            ],
+            speed_cap: None,
+        });
+    }
+
+    #[test]
+    fn test_phase_overshoots_cap() {
+        // The first phase ends at 6 mm/ms, past the cap, and the second must not start from there.
+        let model = RaceModel::Charged {
+            phases: vec![
+                ChargePhase {
+                    rate: 3,
+                    duration: Some(2),
+                },
+                ChargePhase {
+                    rate: 1,
+                    duration: Some(4),
+                },
+            ],
+            speed_cap: Some(4),
+        };
+        let speeds = (0..8).map(|t| model.speed(t)).collect::<Vec<_>>();
+        assert_eq!(speeds, vec![0, 3, 4, 4, 4, 4, 4, 4]);
+        check_model_against_custom(model);
+    }
+
+    #[test]
+    fn test_custom_overflow() {
+        let model = RaceModel::Custom(Box::new(|_| u128::MAX));
+        assert!(model.count_ways(10, 5).is_err());
+    }
+
+    #[test]
+    fn test_count_ways_huge() {
+        // Beyond 2^53 an f64 can't represent every integer, so a float solver loses track of the
+        // roots here.
+        let time = 1 << 60;
+        let lowest = (1 << 20) + 1;
+        let distance = lowest * (time - lowest) - 1;
+        assert_eq!(count_ways(time, distance), time - 2 * lowest + 1);
+
+        // Past 2^64 the square of the time doesn't fit in 128 bits either.
+        let time = 1 << 70;
+        let lowest = (1 << 30) + 1;
+        let distance = lowest * (time - lowest) - 1;
+        assert_eq!(count_ways(time, distance), time - 2 * lowest + 1);
+        assert_eq!(count_ways(1 << 64, 0), (1 << 64) - 1);
+        assert_eq!(count_ways(u128::MAX, 0), u128::MAX - 1);
+    }
+}
@@ -1,13 +1,40 @@
 use clap::Parser;
 use std::process;
 
-use day06::run;
+use day06::{run_with_model, ChargePhase, RaceModel};
 use util::Cli;
 
+#[derive(Parser)]
+struct Day06Cli {
+    #[command(flatten)]
+    cli: Cli,
+
+    /// Speed gained in mm/ms for every ms the button is held.
+    #[arg(long, default_value_t = 1)]
+    acceleration: u128,
+
+    /// Charge in phases instead, each written as rate:duration, e.g. 3:2,1:4,0. A phase without
+    /// a duration lasts until the button is released.
+    #[arg(long, value_delimiter = ',', conflicts_with = "acceleration")]
+    phases: Option<Vec<ChargePhase>>,
+
+    /// Maximum speed of the boat in mm/ms.
+    #[arg(long)]
+    speed_cap: Option<u128>,
+}
+
 fn main() {
-    let cli = Cli::parse();
+    let cli = Day06Cli::parse();
+
+    let model = match cli.phases {
+        Some(phases) => RaceModel::Charged {
+            phases,
+            speed_cap: cli.speed_cap,
+        },
+        None => RaceModel::constant(cli.acceleration, cli.speed_cap),
+    };
 
-    if let Err(e) = run(cli.puzzle_input_path, cli.part_two) {
+    if let Err(e) = run_with_model(cli.cli.puzzle_input_path, cli.cli.part_two, &model) {
         eprintln!("Error: {e}");
         process::exit(1);
     }
@@ -1,195 +1,623 @@
-use std::{collections::HashMap, error::Error, fs, path::PathBuf};
-
-#[derive(PartialEq, PartialOrd, Ord, Eq, Hash, Debug)]
-enum Card {
-    Joker,
-    Two,
-    Three,
-    Four,
-    Five,
-    Six,
-    Seven,
-    Eight,
-    Nine,
-    Ten,
-    J,
-    Q,
-    K,
-    A,
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fs,
+    path::PathBuf,
+    str::FromStr,
+};
+
+/// How two hands of the same type are ordered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    /// Compare the cards one at a time in the order they were dealt.
+    CardByCard,
+    /// Compare the largest group of matching cards first, then the next largest, and so on, with
+    /// wildcards counted as the card they stand in for.
+    Poker,
 }
 
-impl Card {
-    fn from_char(s: &char) -> Result<Self, &'static str> {
-        // Replace jack with joker later
-        match s {
-            'A' => Ok(Card::A),
-            'K' => Ok(Card::K),
-            'Q' => Ok(Card::Q),
-            'J' => Ok(Card::J),
-            'T' => Ok(Card::Ten),
-            '9' => Ok(Card::Nine),
-            '8' => Ok(Card::Eight),
-            '7' => Ok(Card::Seven),
-            '6' => Ok(Card::Six),
-            '5' => Ok(Card::Five),
-            '4' => Ok(Card::Four),
-            '3' => Ok(Card::Three),
-            '2' => Ok(Card::Two),
-            _ => Err("Invalid card char"),
+/// A named type of hand, matched by the sizes of its groups of matching cards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandTypeRule {
+    pub name: String,
+    /// Group sizes, largest first.
+    pub counts: Vec<usize>,
+}
+
+impl HandTypeRule {
+    fn new(name: &str, counts: &[usize]) -> Self {
+        Self {
+            name: name.to_string(),
+            counts: counts.to_vec(),
         }
     }
 }
 
-#[derive(PartialEq, PartialOrd, Eq, Ord)]
-enum HandType {
-    HighCard,
-    OnePair,
-    TwoPair,
-    ThreeKind,
-    FullHouse,
-    FourKind,
-    FiveKind,
+/// The rules of a game of Camel Cards.
+///
+/// Rule sets can be loaded from a config file of `key: value` lines, with `#` comments:
+///
+/// ```text
+/// hand_size: 5
+/// ranking: J23456789TQKA
+/// wildcards: J
+/// tie_break: cards
+/// hand_type: HighCard 1 1 1 1 1
+/// hand_type: OnePair 2 1 1 1
+/// ```
+///
+/// `ranking` lists the cards from lowest to highest, `tie_break` is either `cards` or `poker`,
+/// and `hand_type` lines are listed from lowest to highest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleSet {
+    pub hand_size: usize,
+    /// Every valid card, lowest first.
+    pub ranking: Vec<char>,
+    /// Cards which stand in for whichever card makes the strongest hand.
+    pub wildcards: Vec<char>,
+    pub tie_break: TieBreak,
+    /// Every valid type of hand, lowest first.
+    pub hand_types: Vec<HandTypeRule>,
+}
+
+impl RuleSet {
+    fn standard_hand_types() -> Vec<HandTypeRule> {
+        vec![
+            HandTypeRule::new("HighCard", &[1, 1, 1, 1, 1]),
+            HandTypeRule::new("OnePair", &[2, 1, 1, 1]),
+            HandTypeRule::new("TwoPair", &[2, 2, 1]),
+            HandTypeRule::new("ThreeKind", &[3, 1, 1]),
+            HandTypeRule::new("FullHouse", &[3, 2]),
+            HandTypeRule::new("FourKind", &[4, 1]),
+            HandTypeRule::new("FiveKind", &[5]),
+        ]
+    }
+
+    pub fn part_one() -> Self {
+        Self {
+            hand_size: 5,
+            ranking: "23456789TJQKA".chars().collect(),
+            wildcards: vec![],
+            tie_break: TieBreak::CardByCard,
+            hand_types: Self::standard_hand_types(),
+        }
+    }
+
+    /// Jacks become jokers, which are wild but rank below every other card.
+    pub fn part_two() -> Self {
+        Self {
+            hand_size: 5,
+            ranking: "J23456789TQKA".chars().collect(),
+            wildcards: vec!['J'],
+            tie_break: TieBreak::CardByCard,
+            hand_types: Self::standard_hand_types(),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.ranking.is_empty() {
+            return Err("No cards given in the ranking".to_string());
+        }
+        for (i, c) in self.ranking.iter().enumerate() {
+            if self.ranking[..i].contains(c) {
+                return Err(format!("Card {c:?} is ranked more than once"));
+            }
+        }
+        if let Some(c) = self.wildcards.iter().find(|c| !self.ranking.contains(c)) {
+            return Err(format!("Wildcard {c:?} is not in the ranking"));
+        }
+        if self.hand_types.is_empty() {
+            return Err("No hand types given".to_string());
+        }
+        for hand_type in &self.hand_types {
+            if hand_type.counts.iter().sum::<usize>() != self.hand_size {
+                return Err(format!(
+                    "Hand type {} doesn't have {} cards",
+                    hand_type.name, self.hand_size
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for RuleSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rules = Self::part_one();
+        let mut hand_types = vec![];
+
+        for line in s.lines().map(|l| l.split('#').next().unwrap().trim()) {
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once(':')
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .ok_or_else(|| format!("Invalid rule {line:?}"))?;
+
+            match key {
+                "hand_size" => {
+                    rules.hand_size = value
+                        .parse()
+                        .map_err(|_| format!("Invalid hand size {value:?}"))?
+                }
+                "ranking" => rules.ranking = value.chars().collect(),
+                "wildcards" => rules.wildcards = value.chars().collect(),
+                "tie_break" => {
+                    rules.tie_break = match value {
+                        "cards" => TieBreak::CardByCard,
+                        "poker" => TieBreak::Poker,
+                        _ => return Err(format!("Invalid tie break {value:?}")),
+                    }
+                }
+                "hand_type" => {
+                    let mut words = value.split_whitespace();
+                    let name = words.next().ok_or("Hand type has no name")?;
+                    let mut counts = words
+                        .map(|w| w.parse::<usize>())
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|_| format!("Invalid counts for hand type {name}"))?;
+                    counts.sort_by(|a, b| b.cmp(a));
+                    hand_types.push(HandTypeRule::new(name, &counts));
+                }
+                _ => return Err(format!("Unknown rule {key:?}")),
+            }
+        }
+
+        if !hand_types.is_empty() {
+            rules.hand_types = hand_types;
+        }
+        rules.validate()?;
+        Ok(rules)
+    }
+}
+
+/// A card, represented by its position in the rule set's ranking.
+#[derive(PartialEq, PartialOrd, Ord, Eq, Hash, Debug, Clone, Copy)]
+struct Card(usize);
+
+impl Card {
+    fn from_char(s: &char, rules: &RuleSet) -> Result<Self, &'static str> {
+        rules
+            .ranking
+            .iter()
+            .position(|c| c == s)
+            .map(Card)
+            .ok_or("Invalid card char")
+    }
+
+    fn is_wild(&self, rules: &RuleSet) -> bool {
+        rules.wildcards.contains(&rules.ranking[self.0])
+    }
+}
+
+/// A type of hand, represented by its position in the rule set's hand types.
+#[derive(PartialEq, PartialOrd, Eq, Ord, Debug, Clone, Copy)]
+struct HandType(usize);
+
+/// A group of matching cards, `wild` of which are wildcards standing in for `card`. Groups order
+/// by size and then card, so a sorted list of them compares like a poker tie break.
+#[derive(PartialEq, PartialOrd, Eq, Ord, Hash, Clone)]
+struct Group {
+    count: usize,
+    card: Card,
+    wild: usize,
 }
 
 impl HandType {
-    fn new(cards: &[Card; 5]) -> Result<Self, &'static str> {
+    /// Find the best type of hand the cards can make, along with the cards the wildcards were
+    /// substituted with to make it.
+    fn new(cards: &[Card], rules: &RuleSet) -> Result<(Self, Vec<Card>), String> {
         let mut card_counts = HashMap::new();
 
-        for card in cards {
-            *card_counts.entry(card).or_insert(0) += 1;
+        for card in cards.iter().filter(|c| !c.is_wild(rules)) {
+            *card_counts.entry(*card).or_insert(0) += 1;
+        }
+
+        let mut groups = card_counts
+            .into_iter()
+            .map(|(card, count)| Group {
+                count,
+                card,
+                wild: 0,
+            })
+            .collect::<Vec<_>>();
+        groups.sort_by(|a, b| b.cmp(a));
+
+        // With a custom set of hand types the largest group isn't always the best one to join,
+        // so try every way of adding each wildcard to a group or starting a new one.
+        let wild_count = cards.iter().filter(|c| c.is_wild(rules)).count();
+        let mut assignments = HashSet::from([groups.clone()]);
+        for _ in 0..wild_count {
+            assignments = assignments
+                .iter()
+                .flat_map(|groups| Self::add_wildcard(groups, rules))
+                .collect();
         }
 
-        let joker_count = card_counts.remove(&Card::Joker).unwrap_or(0);
+        let (hand_type, groups) = assignments
+            .into_iter()
+            .filter_map(|groups| {
+                let counts = groups.iter().map(|g| g.count).collect::<Vec<_>>();
+                let i = rules.hand_types.iter().position(|t| t.counts == counts)?;
+                Some((Self(i), groups))
+            })
+            .max()
+            .ok_or_else(|| {
+                let counts = groups.iter().map(|g| g.count).collect::<Vec<_>>();
+                let mut error = format!(
+                    "Invalid hand type for {}, with group sizes {counts:?}",
+                    cards_to_string(cards, rules)
+                );
+                if wild_count > 0 {
+                    error += &format!(" and {wild_count} wildcards");
+                }
+                error
+            })?;
+
+        let mut substitutes = groups
+            .iter()
+            .flat_map(|g| std::iter::repeat_n(g.card, g.wild));
+        let substituted = cards
+            .iter()
+            .map(|c| {
+                if c.is_wild(rules) {
+                    substitutes.next().unwrap()
+                } else {
+                    *c
+                }
+            })
+            .collect::<Vec<_>>();
 
-        let mut counts;
-        if joker_count == 5 {
-            counts = vec![5]
-        } else {
-            counts = card_counts.into_values().collect::<Vec<_>>();
-            counts.sort();
-            *counts.last_mut().unwrap() += joker_count;
+        Ok((hand_type, substituted))
+    }
+
+    /// Every way of adding one more wildcard to the groups, either joining a group or standing in
+    /// for the highest card not already in the hand.
+    fn add_wildcard(groups: &[Group], rules: &RuleSet) -> Vec<Vec<Group>> {
+        let mut options = (0..groups.len())
+            .map(|i| {
+                let mut joined = groups.to_vec();
+                joined[i].count += 1;
+                joined[i].wild += 1;
+                joined
+            })
+            .collect::<Vec<_>>();
+
+        let unused = (0..rules.ranking.len())
+            .map(Card)
+            .filter(|c| groups.iter().all(|g| g.card != *c))
+            .max_by_key(|c| (!c.is_wild(rules), *c));
+        if let Some(card) = unused {
+            let mut extended = groups.to_vec();
+            extended.push(Group {
+                count: 1,
+                card,
+                wild: 1,
+            });
+            options.push(extended);
         }
 
-        match counts.as_slice() {
-            [5] => Ok(Self::FiveKind),
-            [1, 4] => Ok(Self::FourKind),
-            [2, 3] => Ok(Self::FullHouse),
-            [1, 1, 3] => Ok(Self::ThreeKind),
-            [1, 2, 2] => Ok(Self::TwoPair),
-            [1, 1, 1, 2] => Ok(Self::OnePair),
-            [1, 1, 1, 1, 1] => Ok(Self::HighCard),
-            _ => Err("Invalid hand type"),
+        for option in &mut options {
+            option.sort_by(|a, b| b.cmp(a));
         }
+        options
+    }
+
+    fn name<'a>(&self, rules: &'a RuleSet) -> &'a str {
+        &rules.hand_types[self.0].name
     }
 }
 
+fn cards_to_string(cards: &[Card], rules: &RuleSet) -> String {
+    cards.iter().map(|c| rules.ranking[c.0]).collect()
+}
+
 #[derive(PartialEq, PartialOrd, Eq, Ord)]
 struct Hand {
     hand_type: HandType,
-    cards: [Card; 5],
+    /// Cards in the order used to break ties between hands of the same type.
+    tie_break: Vec<Card>,
+    cards: Vec<Card>,
     bid: i64,
+    /// Cards with any wildcards replaced by the card they stand in for.
+    substituted: Vec<Card>,
 }
 
 impl Hand {
-    fn new(s: &str, part_two: bool) -> Result<Self, &'static str> {
-        let (card_str, bid_str) = s.split_once(' ').unwrap();
+    fn new(s: &str, rules: &RuleSet) -> Result<Self, String> {
+        let (card_str, bid_str) = s.split_once(' ').ok_or("Missing bid")?;
 
-        let bid = bid_str.parse::<i64>().unwrap();
+        let bid = bid_str.parse::<i64>().map_err(|_| "Invalid bid")?;
 
         let cards = card_str
             .chars()
-            .map(|c| Card::from_char(&c).unwrap())
-            .map(|c| {
-                // Replace J with joker.
-                if part_two && c == Card::J {
-                    Card::Joker
-                } else {
-                    c
-                }
-            })
-            .collect::<Vec<_>>()
-            .try_into()
-            .unwrap();
+            .map(|c| Card::from_char(&c, rules))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if cards.len() != rules.hand_size {
+            return Err(format!(
+                "Hand {card_str} doesn't have {} cards",
+                rules.hand_size
+            ));
+        }
+
+        let (hand_type, substituted) = HandType::new(&cards, rules)?;
+
+        let tie_break = match rules.tie_break {
+            TieBreak::CardByCard => cards.clone(),
+            TieBreak::Poker => {
+                let mut sorted = substituted.clone();
+                let count = |c: &Card| substituted.iter().filter(|s| *s == c).count();
+                sorted.sort_by_key(|c| std::cmp::Reverse((count(c), *c)));
+                sorted
+            }
+        };
 
-        let hand_type = HandType::new(&cards).unwrap();
         Ok(Self {
-            hand_type: hand_type,
-            cards: cards,
-            bid: bid,
+            hand_type,
+            tie_break,
+            cards,
+            bid,
+            substituted,
         })
     }
+
+    /// Describe the hand's type and any wildcard substitution, and what decided its order against
+    /// the hands ranked just below and just above it.
+    fn explain(&self, below: Option<&Hand>, above: Option<&Hand>, rules: &RuleSet) -> String {
+        let cards = cards_to_string(&self.cards, rules);
+        let mut explanation = format!("{cards} (bid {}) {}", self.bid, self.hand_type.name(rules));
+
+        if self.substituted != self.cards {
+            explanation += &format!(" as {}", cards_to_string(&self.substituted, rules));
+        }
+        if let Some(below) = below {
+            explanation += &self.compare(below, "beats", ">", rules);
+        }
+        if let Some(above) = above {
+            explanation += &self.compare(above, "loses to", "<", rules);
+        }
+
+        explanation
+    }
+
+    /// Describe the card that broke the tie with another hand, or nothing if their types differ.
+    fn compare(&self, other: &Hand, verb: &str, symbol: &str, rules: &RuleSet) -> String {
+        if other.hand_type != self.hand_type {
+            return String::new();
+        }
+
+        let other_cards = cards_to_string(&other.cards, rules);
+        let difference = self
+            .tie_break
+            .iter()
+            .zip(&other.tie_break)
+            .enumerate()
+            .find(|(_, (a, b))| a != b);
+        match difference {
+            Some((i, (a, b))) => format!(
+                ", {verb} {other_cards} on card {} ({} {symbol} {})",
+                i + 1,
+                rules.ranking[a.0],
+                rules.ranking[b.0]
+            ),
+            None => format!(", ties with {other_cards}"),
+        }
+    }
 }
 
-pub fn compute_answer(puzzle_input: &str, part_two: bool) -> i64 {
+/// Parse every hand and sort them from lowest to highest rank.
+fn ranked_hands(puzzle_input: &str, rules: &RuleSet) -> Result<Vec<Hand>, String> {
     let mut hands = puzzle_input
         .lines()
-        .map(|l| Hand::new(l, part_two).unwrap())
-        .collect::<Vec<_>>();
+        .map(|l| Hand::new(l, rules))
+        .collect::<Result<Vec<_>, _>>()?;
 
     hands.sort();
+    Ok(hands)
+}
 
-    hands
+pub fn compute_answer(puzzle_input: &str, rules: &RuleSet) -> Result<i64, String> {
+    let hands = ranked_hands(puzzle_input, rules)?;
+
+    Ok(hands
         .iter()
         .enumerate()
         .map(|(i, h)| (i + 1) as i64 * h.bid)
-        .sum()
+        .sum())
 }
 
 pub fn run(input_path: PathBuf, part_two: bool) -> Result<i64, Box<dyn Error>> {
+    let rules = if part_two {
+        RuleSet::part_two()
+    } else {
+        RuleSet::part_one()
+    };
+    run_with_rules(input_path, &rules)
+}
+
+pub fn run_with_rules(input_path: PathBuf, rules: &RuleSet) -> Result<i64, Box<dyn Error>> {
     let puzzle_input = fs::read_to_string(input_path)?;
-    let answer = compute_answer(&puzzle_input, part_two);
+    let answer = compute_answer(&puzzle_input, rules)?;
     println!("The answer is {answer}");
     Ok(answer)
 }
 
+/// Print every hand in rank order, explaining how it got there.
+pub fn run_explain(input_path: PathBuf, rules: &RuleSet) -> Result<Vec<String>, Box<dyn Error>> {
+    let puzzle_input = fs::read_to_string(input_path)?;
+    let hands = ranked_hands(&puzzle_input, rules)?;
+
+    let explanations = hands
+        .iter()
+        .enumerate()
+        .map(|(i, h)| {
+            let below = i.checked_sub(1).map(|j| &hands[j]);
+            format!("{}: {}", i + 1, h.explain(below, hands.get(i + 1), rules))
+        })
+        .collect::<Vec<_>>();
+
+    for explanation in &explanations {
+        println!("{explanation}");
+    }
+    Ok(explanations)
+}
+
+/// Load a rule set from a config file.
+pub fn load_rules(rules_path: PathBuf) -> Result<RuleSet, Box<dyn Error>> {
+    Ok(RuleSet::from_str(&fs::read_to_string(rules_path)?)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_card_greater_than() {
-        let queen = Card::from_char(&'Q').unwrap();
-        let three = Card::from_char(&'3').unwrap();
+        let rules = RuleSet::part_one();
+        let queen = Card::from_char(&'Q', &rules).unwrap();
+        let three = Card::from_char(&'3', &rules).unwrap();
 
         assert!(queen > three)
     }
 
     #[test]
     fn test_card_equal() {
-        let six_1 = Card::from_char(&'6').unwrap();
-        let six_2 = Card::from_char(&'6').unwrap();
+        let rules = RuleSet::part_one();
+        let six_1 = Card::from_char(&'6', &rules).unwrap();
+        let six_2 = Card::from_char(&'6', &rules).unwrap();
 
         assert!(six_1 == six_2)
     }
 
     #[test]
     fn test_card_less_than() {
-        let two = Card::from_char(&'2').unwrap();
-        let ace = Card::from_char(&'A').unwrap();
+        let rules = RuleSet::part_one();
+        let two = Card::from_char(&'2', &rules).unwrap();
+        let ace = Card::from_char(&'A', &rules).unwrap();
 
         assert!(two < ace);
     }
 
     #[test]
     fn test_hand_ordering_on_type() {
-        let hand_1 = Hand::new("32T3K 765", false).unwrap();
+        let rules = RuleSet::part_one();
+        let hand_1 = Hand::new("32T3K 765", &rules).unwrap();
 
-        let hand_2 = Hand::new("T55J5 684", false).unwrap();
+        let hand_2 = Hand::new("T55J5 684", &rules).unwrap();
 
         assert!(hand_2 > hand_1);
     }
 
     #[test]
     fn test_hand_ordering_on_cards() {
-        let hand_1 = Hand::new("77788 1", false).unwrap();
-        let hand_2 = Hand::new("77888 1", false).unwrap();
+        let rules = RuleSet::part_one();
+        let hand_1 = Hand::new("77788 1", &rules).unwrap();
+        let hand_2 = Hand::new("77888 1", &rules).unwrap();
 
         assert!(hand_2 > hand_1);
     }
 
     #[test]
     fn test_joker_hand_type() {
-        let hand = Hand::new("KTJJT 1", true).unwrap();
-        assert!(hand.hand_type == HandType::FourKind);
+        let rules = RuleSet::part_two();
+        let hand = Hand::new("KTJJT 1", &rules).unwrap();
+        assert!(hand.hand_type.name(&rules) == "FourKind");
+    }
+
+    #[test]
+    fn test_custom_hand_type_wildcards() {
+        // Two pair beats three of a kind here, so the joker should pair the queen rather than
+        // join the kings.
+        let hand_types = vec![
+            HandTypeRule::new("HighCard", &[1, 1, 1, 1, 1]),
+            HandTypeRule::new("OnePair", &[2, 1, 1, 1]),
+            HandTypeRule::new("ThreeKind", &[3, 1, 1]),
+            HandTypeRule::new("TwoPair", &[2, 2, 1]),
+        ];
+        let rules = RuleSet {
+            hand_types: hand_types.clone(),
+            ..RuleSet::part_two()
+        };
+        let hand = Hand::new("KKQJ2 1", &rules).unwrap();
+        assert_eq!(hand.hand_type.name(&rules), "TwoPair");
+        assert_eq!(cards_to_string(&hand.substituted, &rules), "KKQQ2");
+
+        // Without three of a kind, joining the largest group doesn't make a valid hand at all.
+        let rules = RuleSet {
+            hand_types: vec![hand_types[0].clone(), hand_types[3].clone()],
+            ..RuleSet::part_two()
+        };
+        let hand = Hand::new("KKQJ2 1", &rules).unwrap();
+        assert_eq!(hand.hand_type.name(&rules), "TwoPair");
+
+        // A hand of only jokers still becomes five aces under the standard rules.
+        let rules = RuleSet::part_two();
+        let hand = Hand::new("JJJJJ 1", &rules).unwrap();
+        assert_eq!(hand.hand_type.name(&rules), "FiveKind");
+        assert_eq!(cards_to_string(&hand.substituted, &rules), "AAAAA");
+    }
+
+    #[test]
+    fn test_rules_empty_ranking() {
+        let rules = RuleSet {
+            ranking: vec![],
+            wildcards: vec![],
+            ..RuleSet::part_one()
+        };
+        assert!(rules.validate().is_err());
+        assert!(RuleSet::from_str("ranking:").is_err());
+    }
+
+    #[test]
+    fn test_poker_tie_break() {
+        let rules = RuleSet {
+            tie_break: TieBreak::Poker,
+            ..RuleSet::part_one()
+        };
+        // The pair of kings beats the pair of queens, despite the ace.
+        let hand_1 = Hand::new("AQQ23 1", &rules).unwrap();
+        let hand_2 = Hand::new("2KK34 1", &rules).unwrap();
+
+        assert!(hand_2 > hand_1);
+    }
+
+    #[test]
+    fn test_invalid_hand_type() {
+        let rules = RuleSet {
+            hand_types: vec![HandTypeRule::new("FiveKind", &[5])],
+            ..RuleSet::part_one()
+        };
+        let error = Hand::new("KK677 1", &rules).err().unwrap();
+        assert_eq!(
+            error,
+            "Invalid hand type for KK677, with group sizes [2, 2, 1]"
+        );
+    }
+
+    #[test]
+    fn test_rules_from_str() {
+        let config = "# Part two as a config file\n\
+            hand_size: 5\n\
+            ranking: J23456789TQKA\n\
+            wildcards: J\n\
+            tie_break: cards\n\
+            hand_type: HighCard 1 1 1 1 1\n\
+            hand_type: OnePair 2 1 1 1\n\
+            hand_type: TwoPair 2 2 1\n\
+            hand_type: ThreeKind 3 1 1\n\
+            hand_type: FullHouse 3 2\n\
+            hand_type: FourKind 4 1\n\
+            hand_type: FiveKind 5";
+        assert_eq!(RuleSet::from_str(config).unwrap(), RuleSet::part_two());
+    }
+
+    #[test]
+    fn test_rules_invalid_hand_type() {
+        let config = "hand_size: 3\nhand_type: Pair 2 2";
+        assert!(RuleSet::from_str(config).is_err());
     }
 }
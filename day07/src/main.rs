@@ -1,13 +1,41 @@
 use clap::Parser;
-use std::process;
+use std::{path::PathBuf, process};
 
-use day07::run;
+use day07::{load_rules, run_explain, run_with_rules, RuleSet};
 use util::Cli;
 
+#[derive(Parser)]
+struct Day07Cli {
+    #[command(flatten)]
+    cli: Cli,
+
+    /// Load the rules of the game from a config file instead of using the puzzle's rules.
+    #[arg(long)]
+    rules: Option<PathBuf>,
+
+    /// Print every hand in rank order, explaining how it was ranked.
+    #[arg(long)]
+    explain: bool,
+}
+
 fn main() {
-    let cli = Cli::parse();
+    let cli = Day07Cli::parse();
+
+    let rules = match cli.rules {
+        Some(rules_path) => load_rules(rules_path),
+        None if cli.cli.part_two => Ok(RuleSet::part_two()),
+        None => Ok(RuleSet::part_one()),
+    };
+
+    let result = rules.and_then(|rules| {
+        if cli.explain {
+            run_explain(cli.cli.puzzle_input_path, &rules).map(|_| ())
+        } else {
+            run_with_rules(cli.cli.puzzle_input_path, &rules).map(|_| ())
+        }
+    });
 
-    if let Err(e) = run(cli.puzzle_input_path, cli.part_two) {
+    if let Err(e) = result {
         eprintln!("Error: {e}");
         process::exit(1);
     }
@@ -15,3 +15,50 @@ fn test_part_two() {
     let answer = day07::run(input_path, true).unwrap();
     assert_eq!(answer, 5905);
 }
+
+#[test]
+fn test_part_two_from_config() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let mut rules_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    rules_path.push("tests/data/part_two_rules.txt");
+    let rules = day07::load_rules(rules_path).unwrap();
+    let answer = day07::run_with_rules(input_path, &rules).unwrap();
+    assert_eq!(answer, 5905);
+}
+
+#[test]
+fn test_explain_part_two() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let explanations = day07::run_explain(input_path, &day07::RuleSet::part_two()).unwrap();
+    assert_eq!(
+        explanations,
+        vec![
+            "1: 32T3K (bid 765) OnePair",
+            "2: KK677 (bid 28) TwoPair",
+            "3: T55J5 (bid 684) FourKind as T5555, loses to QQQJA on card 1 (T < Q)",
+            "4: QQQJA (bid 483) FourKind as QQQQA, beats T55J5 on card 1 (Q > T), \
+                loses to KTJJT on card 1 (Q < K)",
+            "5: KTJJT (bid 220) FourKind as KTTTT, beats QQQJA on card 1 (K > Q)",
+        ]
+    );
+}
+
+#[test]
+fn test_explain_part_one() {
+    // The lowest and highest ranked hands only have a neighbour on one side.
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let explanations = day07::run_explain(input_path, &day07::RuleSet::part_one()).unwrap();
+    assert_eq!(
+        explanations,
+        vec![
+            "1: 32T3K (bid 765) OnePair",
+            "2: KTJJT (bid 220) TwoPair, loses to KK677 on card 2 (T < K)",
+            "3: KK677 (bid 28) TwoPair, beats KTJJT on card 2 (K > T)",
+            "4: T55J5 (bid 684) ThreeKind, loses to QQQJA on card 1 (T < Q)",
+            "5: QQQJA (bid 483) ThreeKind, beats T55J5 on card 1 (Q > T)",
+        ]
+    );
+}
@@ -1,62 +1,614 @@
-use std::{collections::HashMap, error::Error, fs, path::PathBuf};
+use std::{collections::HashMap, error::Error, fmt, fs, path::PathBuf, str::FromStr};
 
 use num::integer::lcm;
 
-fn compute_answer(puzzle_input: &str, part_two: bool) -> i64 {
-    let (instruction_str, nodes) = puzzle_input.split_once("\n\n").unwrap();
+/// The desert network, with node names interned into indices so that walking the network is
+/// just indexing into a vector.
+pub struct Network {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    /// Left and right neighbours of each node.
+    edges: Vec<[usize; 2]>,
+}
 
-    let mut node_map: HashMap<&str, (&str, &str)> = HashMap::new();
+impl Network {
+    fn intern(&mut self, name: &str) -> usize {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = self.names.len();
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        self.edges.push([id, id]);
+        id
+    }
 
-    // Using a string-based node lookup to minimise writing.
-    for n in nodes.lines() {
-        node_map.insert(&n[..3], (&n[7..10], &n[12..15]));
+    pub fn len(&self) -> usize {
+        self.names.len()
     }
 
-    let starting_nodes;
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
 
-    if part_two {
-        starting_nodes = node_map
-            .keys()
-            .filter(|k| k.chars().nth(2).unwrap() == 'A')
-            .map(|k| *k)
-            .collect::<Vec<_>>()
-    } else {
-        starting_nodes = vec!["AAA"];
+    pub fn id(&self, name: &str) -> Option<usize> {
+        self.ids.get(name).copied()
     }
 
-    let mut step_counts: Vec<i64> = vec![];
+    pub fn name(&self, id: usize) -> &str {
+        &self.names[id]
+    }
 
-    for n in starting_nodes.into_iter() {
-        let mut current_node = n;
-        let mut steps = 0;
-        for instruction in instruction_str.chars().cycle() {
-            current_node = match instruction {
-                'L' => node_map[current_node].0,
-                'R' => node_map[current_node].1,
-                _ => panic!("Invalid instruction!"),
-            };
-            steps += 1;
-            if part_two {
-                if current_node.chars().nth(2).unwrap() == 'Z' {
-                    step_counts.push(steps);
-                    break;
+    /// The node reached by taking an instruction (0 for left, 1 for right) from a node.
+    pub fn step(&self, id: usize, instruction: usize) -> usize {
+        self.edges[id][instruction]
+    }
+
+    /// Find the strongly connected components of the network using Tarjan's algorithm, with an
+    /// explicit stack so that large networks can't overflow the call stack.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<usize>> {
+        let unvisited = usize::MAX;
+        let mut index = vec![unvisited; self.len()];
+        let mut low_link = vec![0; self.len()];
+        let mut on_stack = vec![false; self.len()];
+        let mut stack = vec![];
+        let mut components = vec![];
+        let mut next_index = 0;
+
+        for root in 0..self.len() {
+            if index[root] != unvisited {
+                continue;
+            }
+
+            // Each frame holds a node and which of its edges to follow next.
+            let mut call_stack = vec![(root, 0)];
+            index[root] = next_index;
+            low_link[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+
+            while let Some(frame) = call_stack.last_mut() {
+                let v = frame.0;
+                if frame.1 < 2 {
+                    let w = self.edges[v][frame.1];
+                    frame.1 += 1;
+                    if index[w] == unvisited {
+                        index[w] = next_index;
+                        low_link[w] = next_index;
+                        next_index += 1;
+                        stack.push(w);
+                        on_stack[w] = true;
+                        call_stack.push((w, 0));
+                    } else if on_stack[w] {
+                        low_link[v] = low_link[v].min(index[w]);
+                    }
+                } else {
+                    call_stack.pop();
+                    if let Some(&(parent, _)) = call_stack.last() {
+                        low_link[parent] = low_link[parent].min(low_link[v]);
+                    }
+                    if low_link[v] == index[v] {
+                        let mut component = vec![];
+                        loop {
+                            let w = stack.pop().unwrap();
+                            on_stack[w] = false;
+                            component.push(w);
+                            if w == v {
+                                break;
+                            }
+                        }
+                        components.push(component);
+                    }
                 }
+            }
+        }
+
+        components
+    }
+
+    /// Render the network as a Graphviz DOT graph, with start nodes in green and end nodes in red.
+    /// Any loops in the network are drawn in their own cluster. If a path is given as a list of
+    /// (node, instruction) steps, its edges are coloured from red to blue by the step they were
+    /// first taken.
+    pub fn to_dot(&self, starts: &[usize], is_end: &[bool], path: &[(usize, usize)]) -> String {
+        let quote = |id: usize| format!("\"{}\"", self.name(id).replace('"', "\\\""));
+
+        let mut first_taken = HashMap::new();
+        for (step, &edge) in path.iter().enumerate() {
+            first_taken.entry(edge).or_insert(step);
+        }
+
+        let mut dot = String::from("digraph network {\n");
+
+        let node = |id: usize| {
+            let style = if starts.contains(&id) {
+                " [style=filled, fillcolor=green]"
+            } else if is_end[id] {
+                " [style=filled, fillcolor=red]"
             } else {
-                if current_node == "ZZZ" {
-                    step_counts.push(steps);
-                    break;
-                }
+                ""
+            };
+            format!("{}{};\n", quote(id), style)
+        };
+
+        let mut in_cluster = vec![false; self.len()];
+        let loops = self
+            .strongly_connected_components()
+            .into_iter()
+            .filter(|c| c.len() > 1 || self.edges[c[0]].contains(&c[0]));
+        for (i, component) in loops.enumerate() {
+            dot += &format!("    subgraph cluster_{i} {{\n");
+            for &id in component.iter().rev() {
+                in_cluster[id] = true;
+                dot += &format!("        {}", node(id));
+            }
+            dot += "    }\n";
+        }
+
+        for id in (0..self.len()).filter(|&id| !in_cluster[id]) {
+            dot += &format!("    {}", node(id));
+        }
+
+        for id in 0..self.len() {
+            let [left, right] = self.edges[id];
+            let mut edges = vec![(vec![0], left)];
+            if left == right {
+                edges[0].0.push(1);
+            } else {
+                edges.push((vec![1], right));
+            }
+
+            for (instructions, to) in edges {
+                let label = instructions
+                    .iter()
+                    .map(|&i| if i == 0 { "L" } else { "R" })
+                    .collect::<Vec<_>>()
+                    .join("/");
+                let step = instructions
+                    .iter()
+                    .filter_map(|&i| first_taken.get(&(id, i)))
+                    .min();
+                let attributes = match step {
+                    Some(step) => {
+                        let hue = 0.66 * *step as f64 / path.len().max(1) as f64;
+                        format!(
+                            "label=\"{label} (step {})\", color=\"{hue:.3} 1.000 1.000\", penwidth=2",
+                            step + 1
+                        )
+                    }
+                    None => format!("label=\"{label}\""),
+                };
+                dot += &format!("    {} -> {} [{attributes}];\n", quote(id), quote(to));
+            }
+        }
+
+        dot += "}\n";
+        dot
+    }
+}
+
+impl FromStr for Network {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut network = Self {
+            names: vec![],
+            ids: HashMap::new(),
+            edges: vec![],
+        };
+        let mut defined = vec![];
+
+        for line in s.lines().filter(|l| !l.trim().is_empty()) {
+            let (name, neighbours) = line
+                .split_once(" = ")
+                .ok_or_else(|| format!("Invalid node {line:?}"))?;
+            let (left, right) = neighbours
+                .strip_prefix('(')
+                .and_then(|n| n.strip_suffix(')'))
+                .and_then(|n| n.split_once(", "))
+                .ok_or_else(|| format!("Invalid neighbours for node {name}"))?;
+
+            let id = network.intern(name.trim());
+            let edges = [network.intern(left.trim()), network.intern(right.trim())];
+
+            defined.resize(network.len(), false);
+            if defined[id] {
+                return Err(format!("Node {name} is defined more than once"));
+            }
+            defined[id] = true;
+            network.edges[id] = edges;
+        }
+
+        if let Some(id) = defined.iter().position(|d| !d) {
+            return Err(format!(
+                "Node {} is referenced but never defined",
+                network.name(id)
+            ));
+        }
+
+        Ok(network)
+    }
+}
+
+/// Parse the instruction string into indices into a node's neighbours.
+pub fn parse_instructions(s: &str) -> Result<Vec<usize>, String> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err("No instructions given".to_string());
+    }
+    s.chars()
+        .map(|c| match c {
+            'L' => Ok(0),
+            'R' => Ok(1),
+            _ => Err(format!("Invalid instruction {c:?}")),
+        })
+        .collect()
+}
+
+/// Count the steps taken from `start` until reaching a node for which `is_end` is true.
+/// Returns `None` if the walk loops forever without reaching an end node.
+fn count_steps(
+    network: &Network,
+    instructions: &[usize],
+    start: usize,
+    is_end: &[bool],
+) -> Option<i64> {
+    // There are only so many (node, instruction index) states, so if we've taken more steps than
+    // that we must be going round in circles.
+    let max_steps = network.len() * instructions.len();
+
+    let mut current_node = start;
+    for (steps, &instruction) in instructions.iter().cycle().take(max_steps).enumerate() {
+        current_node = network.step(current_node, instruction);
+        if is_end[current_node] {
+            return Some(steps as i64 + 1);
+        }
+    }
+    None
+}
+
+/// The path of a ghost from its starting node. Since there are finitely many
+/// (node, instruction index) states the path must eventually settle into a cycle.
+pub struct GhostCycle {
+    pub start: String,
+    /// Steps taken before entering the cycle.
+    pub tail_length: u64,
+    /// Steps taken to go once round the cycle.
+    pub cycle_length: u64,
+    /// Steps at which the ghost is on an end node before entering the cycle.
+    pub tail_hits: Vec<u64>,
+    /// Steps at which the ghost is on an end node during its first time round the cycle.
+    pub cycle_hits: Vec<u64>,
+}
+
+impl GhostCycle {
+    fn new(network: &Network, instructions: &[usize], start: usize, is_end: &[bool]) -> Self {
+        let mut first_seen = HashMap::new();
+        let mut hits = vec![];
+
+        let mut current_node = start;
+        let mut steps = 0;
+        let tail_length = loop {
+            let state = (current_node, steps as usize % instructions.len());
+            if let Some(&seen) = first_seen.get(&state) {
+                break seen;
+            }
+            first_seen.insert(state, steps);
+            if steps > 0 && is_end[current_node] {
+                hits.push(steps);
             }
+            current_node = network.step(current_node, instructions[state.1]);
+            steps += 1;
+        };
+
+        let (tail_hits, cycle_hits) = hits.into_iter().partition(|&h| h < tail_length);
+        Self {
+            start: network.name(start).to_string(),
+            tail_length,
+            cycle_length: steps - tail_length,
+            tail_hits,
+            cycle_hits,
+        }
+    }
+
+    fn first_hit(&self) -> Option<u64> {
+        self.tail_hits.first().or(self.cycle_hits.first()).copied()
+    }
+
+    /// Whether the ghost is on an end node at exactly the multiples of its first hit, which is
+    /// what taking the LCM of every ghost's first hit relies on.
+    pub fn lcm_compatible(&self) -> bool {
+        let Some(first) = self.first_hit() else {
+            return false;
+        };
+        let multiples = |from: u64, to: u64| {
+            (from..to)
+                .filter(|s| *s > 0 && s % first == 0)
+                .collect::<Vec<_>>()
+        };
+
+        self.cycle_length.is_multiple_of(first)
+            && self.tail_hits == multiples(0, self.tail_length)
+            && self.cycle_hits == multiples(self.tail_length, self.tail_length + self.cycle_length)
+    }
+}
+
+impl fmt::Display for GhostCycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: tail {}, cycle {}, tail hits {:?}, cycle hits {:?}",
+            self.start, self.tail_length, self.cycle_length, self.tail_hits, self.cycle_hits
+        )?;
+        if !self.lcm_compatible() {
+            write!(f, " (LCM answer will be wrong)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Extended Euclidean algorithm, returning (g, x, y) such that a * x + b * y = g = gcd(a, b).
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, x, y) = extended_gcd(b, a % b);
+        (g, y, x - (a / b) * y)
+    }
+}
+
+/// Combine t ≡ a1 (mod m1) and t ≡ a2 (mod m2) into a single congruence, if there is a solution.
+fn combine_congruences((a1, m1): (i128, i128), (a2, m2): (i128, i128)) -> Option<(i128, i128)> {
+    let (g, x, _) = extended_gcd(m1, m2);
+    if (a2 - a1) % g != 0 {
+        return None;
+    }
+    let m = m1 / g * m2;
+    let a = (a1 + (a2 - a1) / g * x % (m2 / g) * m1).rem_euclid(m);
+    Some((a, m))
+}
+
+/// Find the first step at which every ghost is on an end node at the same time, without assuming
+/// anything about the shape of their paths.
+fn exact_answer(ghosts: &[GhostCycle]) -> Option<u64> {
+    let settled = ghosts.iter().map(|g| g.tail_length).max().unwrap_or(0);
+    let is_hit = |g: &GhostCycle, step: u64| {
+        if step < g.tail_length {
+            g.tail_hits.contains(&step)
+        } else {
+            g.cycle_hits
+                .iter()
+                .any(|h| (step - g.tail_length) % g.cycle_length == h - g.tail_length)
         }
+    };
+
+    // Before every ghost has entered its cycle, just check each step directly.
+    if let Some(step) = (1..settled).find(|&s| ghosts.iter().all(|g| is_hit(g, s))) {
+        return Some(step);
+    }
+
+    // After that every ghost is periodic, so solve for the steps that land on a hit for all of
+    // them with the Chinese remainder theorem.
+    let mut congruences = vec![(0, 1)];
+    for g in ghosts {
+        congruences = congruences
+            .iter()
+            .flat_map(|&c| {
+                g.cycle_hits.iter().filter_map(move |&h| {
+                    combine_congruences(c, (h as i128, g.cycle_length as i128))
+                })
+            })
+            .collect();
+        congruences.sort();
+        congruences.dedup();
+    }
+
+    let settled = settled.max(1) as i128;
+    congruences
+        .into_iter()
+        .map(|(a, m)| a + ((settled - a).max(0) + m - 1) / m * m)
+        .min()
+        .map(|s| s as u64)
+}
+
+/// The parsed puzzle, along with the nodes the walk starts from and which nodes it ends on.
+struct Puzzle {
+    instructions: Vec<usize>,
+    network: Network,
+    starting_nodes: Vec<usize>,
+    is_end: Vec<bool>,
+}
+
+fn parse_input(puzzle_input: &str, part_two: bool) -> Result<Puzzle, String> {
+    let (instruction_str, nodes) = puzzle_input
+        .split_once("\n\n")
+        .ok_or("Missing instructions")?;
+
+    let instructions = parse_instructions(instruction_str)?;
+    let network = Network::from_str(nodes)?;
+
+    let starting_nodes;
+    let is_end;
+
+    if part_two {
+        starting_nodes = (0..network.len())
+            .filter(|&id| network.name(id).ends_with('A'))
+            .collect::<Vec<_>>();
+        is_end = (0..network.len())
+            .map(|id| network.name(id).ends_with('Z'))
+            .collect::<Vec<_>>();
+    } else {
+        starting_nodes = vec![network.id("AAA").ok_or("Missing node AAA")?];
+        is_end = (0..network.len())
+            .map(|id| network.name(id) == "ZZZ")
+            .collect::<Vec<_>>();
     }
 
+    Ok(Puzzle {
+        instructions,
+        network,
+        starting_nodes,
+        is_end,
+    })
+}
+
+fn compute_answer(puzzle_input: &str, part_two: bool) -> Result<i64, String> {
+    let puzzle = parse_input(puzzle_input, part_two)?;
+    let network = &puzzle.network;
+
+    let step_counts = puzzle
+        .starting_nodes
+        .iter()
+        .map(|&n| {
+            count_steps(network, &puzzle.instructions, n, &puzzle.is_end)
+                .ok_or_else(|| format!("Node {} never reaches an end node", network.name(n)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
     // LCM will find the step where each cycle coincides at the end nodes.
-    step_counts.iter().fold(1, |a, b| lcm(a, *b))
+    Ok(step_counts.iter().fold(1, |a, b| lcm(a, *b)))
+}
+
+fn ghost_cycles(puzzle_input: &str, part_two: bool) -> Result<Vec<GhostCycle>, String> {
+    let puzzle = parse_input(puzzle_input, part_two)?;
+
+    Ok(puzzle
+        .starting_nodes
+        .iter()
+        .map(|&n| GhostCycle::new(&puzzle.network, &puzzle.instructions, n, &puzzle.is_end))
+        .collect())
 }
 
 pub fn run(input_path: PathBuf, part_two: bool) -> Result<i64, Box<dyn Error>> {
     let puzzle_input = fs::read_to_string(input_path)?;
-    let answer = compute_answer(&puzzle_input, part_two);
+    let answer = compute_answer(&puzzle_input, part_two)?;
     println!("The answer is {answer}");
     Ok(answer)
 }
+
+/// Print the cycle each ghost settles into, flagging any that break the LCM answer.
+pub fn run_cycle_report(
+    input_path: PathBuf,
+    part_two: bool,
+) -> Result<Vec<GhostCycle>, Box<dyn Error>> {
+    let puzzle_input = fs::read_to_string(input_path)?;
+    let ghosts = ghost_cycles(&puzzle_input, part_two)?;
+    for g in &ghosts {
+        println!("{g}");
+    }
+    Ok(ghosts)
+}
+
+/// Print the network as a Graphviz DOT graph, optionally with the path taken from a node to the
+/// first end node it reaches.
+pub fn run_dot(
+    input_path: PathBuf,
+    part_two: bool,
+    path_from: Option<&str>,
+) -> Result<String, Box<dyn Error>> {
+    let puzzle_input = fs::read_to_string(input_path)?;
+    let puzzle = parse_input(&puzzle_input, part_two)?;
+    let network = &puzzle.network;
+
+    let mut path = vec![];
+    if let Some(name) = path_from {
+        let mut current_node = network
+            .id(name)
+            .ok_or_else(|| format!("Unknown node {name}"))?;
+        let max_steps = network.len() * puzzle.instructions.len();
+        for &instruction in puzzle.instructions.iter().cycle().take(max_steps) {
+            path.push((current_node, instruction));
+            current_node = network.step(current_node, instruction);
+            if puzzle.is_end[current_node] {
+                break;
+            }
+        }
+    }
+
+    let dot = network.to_dot(&puzzle.starting_nodes, &puzzle.is_end, &path);
+    print!("{dot}");
+    Ok(dot)
+}
+
+/// Find the answer from each ghost's full cycle, rather than assuming the LCM shortcut holds.
+pub fn run_exact(input_path: PathBuf, part_two: bool) -> Result<i64, Box<dyn Error>> {
+    let puzzle_input = fs::read_to_string(input_path)?;
+    let ghosts = ghost_cycles(&puzzle_input, part_two)?;
+    let answer = exact_answer(&ghosts).ok_or("The ghosts never all reach end nodes together")?;
+    println!("The answer is {answer}");
+    Ok(answer as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_node_names() {
+        let network =
+            Network::from_str("START = (MIDDLE, START)\nMIDDLE = (START, END)\nEND = (END, END)")
+                .unwrap();
+        let instructions = parse_instructions("LR").unwrap();
+        let is_end = (0..network.len())
+            .map(|id| network.name(id) == "END")
+            .collect::<Vec<_>>();
+        let start = network.id("START").unwrap();
+        assert_eq!(
+            count_steps(&network, &instructions, start, &is_end),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn test_dangling_reference() {
+        assert!(Network::from_str("AAA = (BBB, ZZZ)\nZZZ = (ZZZ, ZZZ)").is_err());
+    }
+
+    #[test]
+    fn test_duplicate_node() {
+        assert!(Network::from_str("AAA = (AAA, AAA)\nAAA = (AAA, AAA)").is_err());
+    }
+
+    #[test]
+    fn test_invalid_instruction() {
+        assert!(parse_instructions("LRX").is_err());
+    }
+
+    #[test]
+    fn test_empty_instructions() {
+        assert!(parse_instructions("").is_err());
+        assert!(parse_instructions("  ").is_err());
+        let puzzle_input = "\n\nAAA = (ZZZ, ZZZ)\nZZZ = (ZZZ, ZZZ)\n";
+        assert!(compute_answer(puzzle_input, false).is_err());
+    }
+
+    #[test]
+    fn test_strongly_connected_components() {
+        let network = Network::from_str(
+            "AAA = (BBB, CCC)\nBBB = (AAA, CCC)\nCCC = (DDD, DDD)\nDDD = (CCC, ZZZ)\nZZZ = (ZZZ, ZZZ)",
+        )
+        .unwrap();
+        let mut components = network
+            .strongly_connected_components()
+            .into_iter()
+            .map(|c| {
+                let mut names = c.iter().map(|&id| network.name(id)).collect::<Vec<_>>();
+                names.sort();
+                names
+            })
+            .collect::<Vec<_>>();
+        components.sort();
+        assert_eq!(
+            components,
+            vec![vec!["AAA", "BBB"], vec!["CCC", "DDD"], vec!["ZZZ"]]
+        );
+    }
+
+    #[test]
+    fn test_unreachable_end() {
+        let network = Network::from_str("AAA = (AAA, AAA)\nZZZ = (ZZZ, ZZZ)").unwrap();
+        let is_end = vec![false, true];
+        assert_eq!(count_steps(&network, &[0, 1], 0, &is_end), None);
+    }
+}
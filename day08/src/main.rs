@@ -1,13 +1,46 @@
 use clap::Parser;
 use std::process;
 
-use day08::run;
+use day08::{run, run_cycle_report, run_dot, run_exact};
 use util::Cli;
 
+#[derive(Parser)]
+struct Day08Cli {
+    #[command(flatten)]
+    cli: Cli,
+
+    /// Print the cycle each starting node settles into.
+    #[arg(long)]
+    cycles: bool,
+
+    /// Solve using each starting node's full cycle instead of assuming the LCM shortcut holds.
+    #[arg(long)]
+    exact: bool,
+
+    /// Print the network as a Graphviz DOT graph.
+    #[arg(long)]
+    dot: bool,
+
+    /// Highlight the path taken from this node in the DOT graph.
+    #[arg(long, requires = "dot")]
+    path_from: Option<String>,
+}
+
 fn main() {
-    let cli = Cli::parse();
+    let cli = Day08Cli::parse();
+    let (path, part_two) = (cli.cli.puzzle_input_path, cli.cli.part_two);
+
+    let result = if cli.dot {
+        run_dot(path, part_two, cli.path_from.as_deref()).map(|_| ())
+    } else if cli.cycles {
+        run_cycle_report(path, part_two).map(|_| ())
+    } else if cli.exact {
+        run_exact(path, part_two).map(|_| ())
+    } else {
+        run(path, part_two).map(|_| ())
+    };
 
-    if let Err(e) = run(cli.puzzle_input_path, cli.part_two) {
+    if let Err(e) = result {
         eprintln!("Error: {e}");
         process::exit(1);
     }
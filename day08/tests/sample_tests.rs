@@ -25,3 +25,47 @@ fn test_part_two() {
     let answer = day08::run(input_path, true).unwrap();
     assert_eq!(answer, 6);
 }
+
+#[test]
+fn test_part_two_cycle_report() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input_3.txt");
+    let ghosts = day08::run_cycle_report(input_path, true).unwrap();
+    assert!(ghosts.iter().all(|g| g.lcm_compatible()));
+
+    let ghost = ghosts.iter().find(|g| g.start == "22A").unwrap();
+    assert_eq!((ghost.tail_length, ghost.cycle_length), (1, 6));
+    assert_eq!(ghost.cycle_hits, vec![3, 6]);
+}
+
+#[test]
+fn test_part_two_exact() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input_3.txt");
+    let answer = day08::run_exact(input_path, true).unwrap();
+    assert_eq!(answer, 6);
+}
+
+#[test]
+fn test_part_two_lcm_incompatible() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input_4.txt");
+    let ghosts = day08::run_cycle_report(input_path.clone(), true).unwrap();
+    assert!(!ghosts.iter().all(|g| g.lcm_compatible()));
+
+    let answer = day08::run_exact(input_path, true).unwrap();
+    assert_eq!(answer, 4);
+}
+
+#[test]
+fn test_dot_with_path() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input_2.txt");
+    let dot = day08::run_dot(input_path, false, Some("AAA")).unwrap();
+    assert!(dot.starts_with("digraph network {"));
+    assert!(dot.contains("subgraph cluster_"));
+    assert!(dot.contains("\"AAA\" [style=filled, fillcolor=green];"));
+    assert!(dot.contains("\"ZZZ\" [style=filled, fillcolor=red];"));
+    assert!(dot.contains("\"BBB\" -> \"ZZZ\" [label=\"R (step 6)\""));
+    assert!(dot.contains("\"ZZZ\" -> \"ZZZ\" [label=\"L/R\"];"));
+}
@@ -1,67 +1,375 @@
-use std::{error::Error, fs, path::PathBuf};
+use std::{error::Error, fmt, fs, io::BufRead, path::PathBuf, str::FromStr};
 
-fn recursive_diff(values: &Vec<i64>) -> i64 {
-    let diff = values[..]
-        .windows(2)
-        .map(|v| v[1] - v[0])
-        .collect::<Vec<_>>();
+use num::{BigInt, BigRational, One, Zero};
 
-    if diff.iter().all(|v| *v == 0) {
-        0
-    } else {
-        recursive_diff(&diff) + diff.last().unwrap()
+/// The polynomial underlying an OASIS sequence, stored as the leading diagonal of its difference
+/// table. This is the Newton forward difference form, where the value at index x is the sum over
+/// k of `differences[k] * C(x, k)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceModel {
+    differences: Vec<BigInt>,
+}
+
+impl SequenceModel {
+    /// Fit a polynomial to the values, which are taken to be at indices 0, 1, 2, ...
+    /// Returns an error if the difference table never reaches a row of zeroes, as the degree of
+    /// the polynomial can't be confirmed.
+    pub fn new(values: &[i64]) -> Result<Self, String> {
+        let mut row = values.iter().map(|&v| BigInt::from(v)).collect::<Vec<_>>();
+        let mut differences = vec![];
+
+        while !row.iter().all(|v| v.is_zero()) || row.is_empty() {
+            if row.len() < 2 {
+                return Err(format!(
+                    "Sequence {values:?} never reaches a zero difference row"
+                ));
+            }
+            differences.push(row[0].clone());
+            row = row.windows(2).map(|v| &v[1] - &v[0]).collect();
+        }
+
+        Ok(Self { differences })
+    }
+
+    /// Degree of the polynomial, with the zero polynomial given degree 0.
+    pub fn degree(&self) -> usize {
+        self.differences.len().saturating_sub(1)
+    }
+
+    /// Value of the sequence at any index, including negative ones.
+    pub fn evaluate(&self, index: &BigInt) -> BigInt {
+        let mut value = BigInt::zero();
+        // Generalised binomial coefficient C(index, k), which is always an integer.
+        let mut binomial = BigInt::one();
+        for (k, d) in self.differences.iter().enumerate() {
+            value += d * &binomial;
+            binomial = binomial * (index - k) / (k + 1);
+        }
+        value
+    }
+
+    /// Coefficients of the polynomial in powers of the index, lowest power first.
+    pub fn coefficients(&self) -> Vec<BigRational> {
+        let mut coefficients = vec![BigRational::zero(); self.differences.len()];
+
+        // Coefficients of the falling factorial index * (index - 1) * ... * (index - k + 1),
+        // divided by k!, which is C(index, k).
+        let mut binomial = vec![BigRational::one()];
+        for (k, d) in self.differences.iter().enumerate() {
+            let d = BigRational::from_integer(d.clone());
+            for (c, b) in coefficients.iter_mut().zip(&binomial) {
+                *c += &d * b;
+            }
+
+            let k = BigRational::from_integer(BigInt::from(k));
+            let divisor = &k + BigRational::one();
+            let mut next = vec![BigRational::zero(); binomial.len() + 1];
+            for (i, b) in binomial.iter().enumerate() {
+                next[i + 1] += b / &divisor;
+                next[i] -= b * &k / &divisor;
+            }
+            binomial = next;
+        }
+
+        coefficients
     }
 }
 
-fn parse_line(line: &str, part_two: bool) -> i64 {
-    let mut values = line
-        .split(" ")
-        .map(|s| s.parse::<i64>().unwrap())
-        .collect::<Vec<_>>();
+impl fmt::Display for SequenceModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let terms = self
+            .coefficients()
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| !c.is_zero())
+            .map(|(power, c)| match power {
+                0 => format!("{c}"),
+                1 => format!("({c})x"),
+                _ => format!("({c})x^{power}"),
+            })
+            .collect::<Vec<_>>();
 
-    if part_two {
-        values.reverse();
+        if terms.is_empty() {
+            write!(f, "degree {}: 0", self.degree())
+        } else {
+            write!(f, "degree {}: {}", self.degree(), terms.join(" + "))
+        }
     }
+}
 
-    values.last().unwrap() + recursive_diff(&values)
+/// Extrapolates a sequence as its values arrive one at a time, keeping only the trailing diagonal
+/// of the difference table (the last entry of each row) along with the leading diagonal needed to
+/// extrapolate backwards. Arithmetic is checked, so long sequences report overflow instead of
+/// silently wrapping.
+#[derive(Debug, Clone, Default)]
+pub struct StreamingExtrapolator {
+    /// Last entry of each row of the difference table, starting with the values themselves.
+    last: Vec<i128>,
+    /// First entry of each row of the difference table.
+    first: Vec<i128>,
+    /// Index of the last non-zero entry in `first`.
+    last_nonzero: Option<usize>,
 }
-fn compute_answer(puzzle_input: &str, part_two: bool) -> i64 {
+
+impl StreamingExtrapolator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next value of the sequence in.
+    pub fn push(&mut self, value: i64) -> Result<(), String> {
+        let mut entry = value as i128;
+        for last in self.last.iter_mut() {
+            let difference = entry
+                .checked_sub(*last)
+                .ok_or("Overflow computing differences")?;
+            *last = entry;
+            entry = difference;
+        }
+
+        // Each new value extends the difference table by one row.
+        if entry != 0 {
+            self.last_nonzero = Some(self.first.len());
+        }
+        self.last.push(entry);
+        self.first.push(entry);
+        Ok(())
+    }
+
+    /// Number of values seen so far.
+    pub fn len(&self) -> usize {
+        self.last.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.last.is_empty()
+    }
+
+    /// Rows of the table needed to extrapolate, or `None` if the table hasn't reached a row of
+    /// zeroes yet.
+    fn settled_rows(&self) -> Option<usize> {
+        // Row r is all zero if its first entry and every later entry of the leading diagonal are
+        // zero, as each of those rows is the differences of the one above.
+        let rows = self.last_nonzero.map_or(0, |i| i + 1);
+        (rows < self.len()).then_some(rows)
+    }
+
+    /// The value predicted to come next, if the sequence has settled.
+    pub fn next(&self) -> Result<Option<i128>, String> {
+        let Some(rows) = self.settled_rows() else {
+            return Ok(None);
+        };
+        self.last[..rows]
+            .iter()
+            .try_fold(0i128, |acc, v| acc.checked_add(*v))
+            .map(Some)
+            .ok_or_else(|| "Overflow extrapolating forwards".to_string())
+    }
+
+    /// The value predicted to have come before the first, if the sequence has settled.
+    pub fn previous(&self) -> Result<Option<i128>, String> {
+        let Some(rows) = self.settled_rows() else {
+            return Ok(None);
+        };
+        self.first[..rows]
+            .iter()
+            .rev()
+            .try_fold(0i128, |acc, v| v.checked_sub(acc))
+            .map(Some)
+            .ok_or_else(|| "Overflow extrapolating backwards".to_string())
+    }
+}
+
+fn parse_values(line: &str) -> Result<Vec<i64>, String> {
+    line.split_whitespace()
+        .map(|s| {
+            s.parse::<i64>()
+                .map_err(|e| format!("Invalid value {s:?}: {e}"))
+        })
+        .collect()
+}
+
+impl FromStr for SequenceModel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(&parse_values(s)?)
+    }
+}
+
+fn parse_line(line: &str, part_two: bool) -> Result<i64, String> {
+    let values = parse_values(line)?;
+    let model = SequenceModel::new(&values)?;
+
+    let index = if part_two { -1 } else { values.len() as i64 };
+
+    i64::try_from(model.evaluate(&BigInt::from(index))).map_err(|e| e.to_string())
+}
+
+fn compute_answer(puzzle_input: &str, part_two: bool) -> Result<i64, String> {
     puzzle_input.lines().map(|l| parse_line(l, part_two)).sum()
 }
 
 pub fn run(input_path: PathBuf, part_two: bool) -> Result<i64, Box<dyn Error>> {
     let puzzle_input = fs::read_to_string(input_path)?;
-    let answer = compute_answer(&puzzle_input, part_two);
+    let answer = compute_answer(&puzzle_input, part_two)?;
+    println!("The answer is {answer}");
+    Ok(answer)
+}
+
+/// Sum the value of every sequence at the given index.
+pub fn run_at(input_path: PathBuf, index: &BigInt) -> Result<BigInt, Box<dyn Error>> {
+    let puzzle_input = fs::read_to_string(input_path)?;
+    let answer = puzzle_input
+        .lines()
+        .map(|l| Ok(SequenceModel::from_str(l)?.evaluate(index)))
+        .sum::<Result<BigInt, String>>()?;
     println!("The answer is {answer}");
     Ok(answer)
 }
 
+/// Read values one at a time, such as from a pipe, printing the predicted next and previous values
+/// after each one. Returns the final predictions.
+pub fn run_stream(reader: impl BufRead) -> Result<(Option<i128>, Option<i128>), Box<dyn Error>> {
+    let mut extrapolator = StreamingExtrapolator::new();
+    for line in reader.lines() {
+        for value in parse_values(&line?)? {
+            extrapolator.push(value)?;
+            let show = |p: Option<i128>| p.map_or("unknown".to_string(), |p| p.to_string());
+            println!(
+                "{value}: next {}, previous {}",
+                show(extrapolator.next()?),
+                show(extrapolator.previous()?)
+            );
+        }
+    }
+    Ok((extrapolator.next()?, extrapolator.previous()?))
+}
+
+/// Print the polynomial fitted to every sequence.
+pub fn run_fit(input_path: PathBuf) -> Result<Vec<SequenceModel>, Box<dyn Error>> {
+    let puzzle_input = fs::read_to_string(input_path)?;
+    let models = puzzle_input
+        .lines()
+        .map(SequenceModel::from_str)
+        .collect::<Result<Vec<_>, _>>()?;
+    for model in &models {
+        println!("{model}");
+    }
+    Ok(models)
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
 
+    fn next_difference(values: &[i64]) -> BigInt {
+        let model = SequenceModel::new(values).unwrap();
+        model.evaluate(&BigInt::from(values.len())) - values.last().unwrap()
+    }
+
     #[test]
-    fn test_recursive_diff_zero() {
-        let answer = recursive_diff(&vec![1, 1, 1, 1, 1]);
-        assert_eq!(answer, 0);
+    fn test_next_difference_zero() {
+        let answer = next_difference(&[1, 1, 1, 1, 1]);
+        assert_eq!(answer, BigInt::from(0));
     }
 
     #[test]
-    fn test_recursive_diff_nonzero() {
-        let answer = recursive_diff(&vec![10, 13, 16, 21, 30, 45]);
-        assert_eq!(answer, 23);
+    fn test_next_difference_nonzero() {
+        let answer = next_difference(&[10, 13, 16, 21, 30, 45]);
+        assert_eq!(answer, BigInt::from(23));
     }
 
     #[test]
     fn test_parse_line_part_one() {
         let answer = parse_line("10 13 16 21 30 45", false);
-        assert_eq!(answer, 68);
+        assert_eq!(answer, Ok(68));
     }
 
     #[test]
     fn test_parse_line_part_two() {
         let answer = parse_line("10 13 16 21 30 45", true);
-        assert_eq!(answer, 5);
+        assert_eq!(answer, Ok(5));
+    }
+
+    #[test]
+    fn test_degree_and_coefficients() {
+        // 10 + 3x + 2 * C(x, 3) = 10 + 11x / 3 - x^2 + x^3 / 3
+        let model = SequenceModel::from_str("10 13 16 21 30 45").unwrap();
+        assert_eq!(model.degree(), 3);
+        let coefficients = model
+            .coefficients()
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(coefficients, vec!["10", "11/3", "-1", "1/3"]);
+    }
+
+    #[test]
+    fn test_evaluate_far() {
+        let model = SequenceModel::from_str("1 3 6 10 15 21").unwrap();
+        // Triangular numbers, offset by one.
+        let index = BigInt::from(10).pow(20);
+        let expected = (&index + 1) * (&index + 2) / 2;
+        assert_eq!(model.evaluate(&index), expected);
+        assert_eq!(model.evaluate(&BigInt::from(-3)), BigInt::from(1));
+    }
+
+    #[test]
+    fn test_streaming() {
+        let mut extrapolator = StreamingExtrapolator::new();
+        let mut predictions = vec![];
+        for value in [10, 13, 16, 21, 30, 45] {
+            extrapolator.push(value).unwrap();
+            predictions.push(extrapolator.next().unwrap());
+        }
+        assert_eq!(
+            predictions,
+            vec![None, None, Some(19), None, Some(45), Some(68)]
+        );
+        assert_eq!(extrapolator.previous(), Ok(Some(5)));
+    }
+
+    #[test]
+    fn test_streaming_matches_model() {
+        for line in [
+            "0 3 6 9 12 15",
+            "1 3 6 10 15 21",
+            "2 -5 8 -11 14 -17 20 -23",
+        ] {
+            let mut extrapolator = StreamingExtrapolator::new();
+            let values = parse_values(line).unwrap();
+            for value in &values {
+                extrapolator.push(*value).unwrap();
+            }
+            let model = SequenceModel::new(&values).ok();
+            let predict = |index: i64| model.as_ref().map(|m| m.evaluate(&BigInt::from(index)));
+            assert_eq!(
+                extrapolator.next().unwrap().map(BigInt::from),
+                predict(values.len() as i64)
+            );
+            assert_eq!(
+                extrapolator.previous().unwrap().map(BigInt::from),
+                predict(-1)
+            );
+        }
+    }
+
+    #[test]
+    fn test_streaming_overflow() {
+        let mut extrapolator = StreamingExtrapolator::new();
+        let mut result = Ok(());
+        for i in 0..200 {
+            let value = if i % 2 == 0 { i64::MAX } else { i64::MIN };
+            result = result.and(extrapolator.push(value));
+        }
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_no_zero_row() {
+        assert!(SequenceModel::from_str("1 2 4").is_err());
+        assert!(SequenceModel::from_str("7").is_err());
     }
 }
@@ -1,13 +1,48 @@
 use clap::Parser;
-use std::process;
+use num::BigInt;
+use std::{io, path::PathBuf, process};
 
-use day09::run;
-use util::Cli;
+use day09::{run, run_at, run_fit, run_stream};
+
+/// The usual puzzle options, except that the input path isn't needed when streaming from stdin.
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Day09Cli {
+    #[arg(long)]
+    part_two: bool,
+
+    #[arg(required_unless_present = "stream", conflicts_with = "stream")]
+    puzzle_input_path: Option<PathBuf>,
+
+    /// Read one sequence from stdin a value at a time, predicting the next and previous values
+    /// as it goes.
+    #[arg(long, conflicts_with_all = ["part_two", "at", "fit"])]
+    stream: bool,
+
+    /// Sum the value of every sequence at this index instead, counting from zero.
+    #[arg(long, allow_negative_numbers = true)]
+    at: Option<BigInt>,
+
+    /// Print the polynomial fitted to every sequence.
+    #[arg(long)]
+    fit: bool,
+}
 
 fn main() {
-    let cli = Cli::parse();
+    let cli = Day09Cli::parse();
+    let result = if let Some(path) = cli.puzzle_input_path {
+        if cli.fit {
+            run_fit(path).map(|_| ())
+        } else if let Some(index) = cli.at {
+            run_at(path, &index).map(|_| ())
+        } else {
+            run(path, cli.part_two).map(|_| ())
+        }
+    } else {
+        run_stream(io::stdin().lock()).map(|_| ())
+    };
 
-    if let Err(e) = run(cli.puzzle_input_path, cli.part_two) {
+    if let Err(e) = result {
         eprintln!("Error: {e}");
         process::exit(1);
     }
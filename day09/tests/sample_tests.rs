@@ -1,4 +1,6 @@
-use std::path::PathBuf;
+use std::{io::Cursor, path::PathBuf};
+
+use num::BigInt;
 
 #[test]
 fn test_part_one() {
@@ -15,3 +17,33 @@ fn test_part_two() {
     let answer = day09::run(input_path, true).unwrap();
     assert_eq!(answer, 2);
 }
+
+#[test]
+fn test_at_index() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let answer = day09::run_at(input_path, &BigInt::from(-1)).unwrap();
+    assert_eq!(answer, BigInt::from(2));
+}
+
+#[test]
+fn test_fit() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let models = day09::run_fit(input_path).unwrap();
+    let degrees = models.iter().map(|m| m.degree()).collect::<Vec<_>>();
+    assert_eq!(degrees, vec![1, 2, 3]);
+}
+
+#[test]
+fn test_stream() {
+    let input = Cursor::new("10 13\n16 21 30\n45\n");
+    let answer = day09::run_stream(input).unwrap();
+    assert_eq!(answer, (Some(68), Some(5)));
+
+    let input = Cursor::new("1 2 4\n");
+    let answer = day09::run_stream(input).unwrap();
+    assert_eq!(answer, (None, None));
+
+    assert!(day09::run_stream(Cursor::new("1 x\n")).is_err());
+}
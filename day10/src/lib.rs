@@ -36,7 +36,42 @@ struct Position {
     c: usize,
 }
 
+/// How to count the tiles enclosed by the loop in part two.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InteriorMethod {
+    /// Count the loop crossings to the left of every tile separately.
+    CrossingCount,
+    /// Find the area of the loop with the shoelace formula, then use Pick's theorem to get the
+    /// number of tiles inside it.
+    Shoelace,
+    /// Sweep along each row once, tracking whether we're inside the loop.
+    Scanline,
+}
+
+impl FromStr for InteriorMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "crossing-count" => Ok(Self::CrossingCount),
+            "shoelace" => Ok(Self::Shoelace),
+            "scanline" => Ok(Self::Scanline),
+            _ => Err(format!(
+                "Unknown interior method {s}, expected crossing-count, shoelace or scanline"
+            )),
+        }
+    }
+}
+
 impl TileType {
+    /// Whether the tile has a pipe leading north, so crosses a row running just above its centre.
+    fn connects_north(&self) -> bool {
+        matches!(
+            self,
+            Self::Vertical | Self::NorthEastBend | Self::NorthWestBend
+        )
+    }
+
     fn get_offsets(&self) -> [(i32, i32); 2] {
         match self {
             Self::Vertical => [(-1, 0), (1, 0)],
@@ -48,133 +83,337 @@ impl TileType {
             _ => panic!("Invalid tile type for offsets."),
         }
     }
-}
 
-fn compute_answer(puzzle_input: &str, part_two: bool) -> i64 {
-    let tile_map = puzzle_input
-        .lines()
-        .map(|l| {
-            l.chars()
-                .map(|c| TileType::from_str(&c.to_string()).unwrap())
-                .collect::<Vec<_>>()
-        })
-        .collect::<Vec<_>>();
-
-    let n_rows = tile_map.len();
-    let n_cols = tile_map[0].len();
-
-    let starting_position = tile_map
-        .iter()
-        .enumerate()
-        .find_map(|(row_index, row)| {
-            row.iter()
-                .position(|t| *t == TileType::Start)
-                .map(|col_index| Position {
-                    r: row_index,
-                    c: col_index,
-                })
-        })
-        .unwrap();
-
-    let mut neighbouring_positions = vec![];
-
-    let adjacent_offsets = [(1, 0), (0, 1), (-1, 0), (0, -1)];
-
-    for offset in adjacent_offsets {
-        // Check each tile adjacent to the start to find the two tiles which must connect to it.
-
-        // Subtract so we can compare the offsets produced by the neighbouring tile.
-        let r = starting_position.r as i32 - offset.0;
-        let c = starting_position.c as i32 - offset.1;
-
-        if r >= 0 && r < n_rows as i32 && c >= 0 && c < n_cols as i32 {
-            let t = &tile_map[r as usize][c as usize];
-            match t {
-                TileType::Ground => (),
-                _ => {
-                    if t.get_offsets().into_iter().any(|o| o == offset) {
-                        // If the neighbouring tile has one of the same offsets it must connect
-                        // to the start and therefore be part of the loop.
-                        neighbouring_positions.push(Position {
-                            r: r as usize,
-                            c: c as usize,
-                        });
-                    }
-                }
-            }
+    /// Box-drawing glyph for the tile, using heavy lines if `heavy` is set.
+    fn glyph(&self, heavy: bool) -> char {
+        match (self, heavy) {
+            (Self::Vertical, false) => '│',
+            (Self::Vertical, true) => '┃',
+            (Self::Horizontal, false) => '─',
+            (Self::Horizontal, true) => '━',
+            (Self::NorthEastBend, false) => '└',
+            (Self::NorthEastBend, true) => '┗',
+            (Self::NorthWestBend, false) => '┘',
+            (Self::NorthWestBend, true) => '┛',
+            (Self::SouthWestBend, false) => '┐',
+            (Self::SouthWestBend, true) => '┓',
+            (Self::SouthEastBend, false) => '┌',
+            (Self::SouthEastBend, true) => '┏',
+            (Self::Ground, _) => ' ',
+            (Self::Start, _) => 'S',
         }
     }
+}
 
-    let mut last_position = starting_position;
-    let mut current_position = neighbouring_positions[0];
-    let mut step_count = 1;
+struct PipeMaze {
+    tile_map: Vec<Vec<TileType>>,
+    starting_position: Position,
+}
+
+impl FromStr for PipeMaze {
+    type Err = &'static str;
 
-    // Keep track of which tiles are part of the loop for use in part two.
-    let mut loop_tiles = HashSet::new();
-    loop_tiles.insert(last_position);
-    loop_tiles.insert(current_position);
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tile_map = s
+            .lines()
+            .map(|l| {
+                l.chars()
+                    .map(|c| TileType::from_str(&c.to_string()).map_err(|_| "Invalid tile"))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
-    while current_position != starting_position {
-        // Follow the pipe sections around until we get back to the start.
-        let t = &tile_map[current_position.r][current_position.c];
-        let [offset_0, offset_1] = t.get_offsets();
-        let position_0 = Position {
-            r: (current_position.r as i32 + offset_0.0) as usize,
-            c: (current_position.c as i32 + offset_0.1) as usize,
+        let starting_position = tile_map
+            .iter()
+            .enumerate()
+            .find_map(|(row_index, row)| {
+                row.iter()
+                    .position(|t| *t == TileType::Start)
+                    .map(|col_index| Position {
+                        r: row_index,
+                        c: col_index,
+                    })
+            })
+            .ok_or("No starting tile")?;
+
+        let mut maze = Self {
+            tile_map,
+            starting_position,
         };
+        let start_type = maze.infer_start_type()?;
+        maze.tile_map[starting_position.r][starting_position.c] = start_type;
+        Ok(maze)
+    }
+}
 
-        if position_0 == last_position {
-            last_position = current_position;
-            current_position = Position {
-                r: (current_position.r as i32 + offset_1.0) as usize,
-                c: (current_position.c as i32 + offset_1.1) as usize,
+impl PipeMaze {
+    fn n_rows(&self) -> usize {
+        self.tile_map.len()
+    }
+
+    fn n_cols(&self) -> usize {
+        self.tile_map[0].len()
+    }
+
+    /// Move one tile from `position` by `offset`, if that stays on the map.
+    fn step(&self, position: Position, offset: (i32, i32)) -> Option<Position> {
+        let r = position.r.checked_add_signed(offset.0 as isize)?;
+        let c = position.c.checked_add_signed(offset.1 as isize)?;
+        (r < self.n_rows() && c < self.n_cols()).then_some(Position { r, c })
+    }
+
+    /// Work out which pipe is hidden under the start tile. Each pipe type that could connect to
+    /// the start's neighbours is tried in turn, keeping those which lead back round to the start
+    /// from the other side. Exactly one must close a loop.
+    fn infer_start_type(&self) -> Result<TileType, &'static str> {
+        let pipe_types = [
+            TileType::Vertical,
+            TileType::Horizontal,
+            TileType::NorthEastBend,
+            TileType::NorthWestBend,
+            TileType::SouthWestBend,
+            TileType::SouthEastBend,
+        ];
+
+        let mut closing_types = pipe_types
+            .into_iter()
+            .filter(|t| self.trace_loop(t).is_some());
+
+        match (closing_types.next(), closing_types.next()) {
+            (Some(t), None) => Ok(t),
+            (None, _) => Err("Starting tile is not part of a loop"),
+            (Some(_), Some(_)) => {
+                Err("Starting tile is ambiguous, more than one loop passes through it")
             }
-        } else {
+        }
+    }
+
+    /// Follow the pipes out of the start as if it were a `start_type` tile, returning each tile on
+    /// the loop in order, starting with the start tile itself. Returns `None` if the pipes don't
+    /// lead back into the start from its other side.
+    fn trace_loop(&self, start_type: &TileType) -> Option<Vec<Position>> {
+        let starting_position = self.starting_position;
+        let [out_offset, in_offset] = start_type.get_offsets();
+
+        let mut last_position = starting_position;
+        let mut current_position = self.step(starting_position, out_offset)?;
+        let mut loop_positions = vec![last_position];
+
+        while current_position != starting_position {
+            // Follow the pipe sections around until we get back to the start, checking that each
+            // one connects to the last.
+            loop_positions.push(current_position);
+            let t = &self.tile_map[current_position.r][current_position.c];
+            if matches!(t, TileType::Ground | TileType::Start) {
+                return None;
+            }
+            let [offset_0, offset_1] = t.get_offsets();
+            let next_offset = if self.step(current_position, offset_0) == Some(last_position) {
+                offset_1
+            } else if self.step(current_position, offset_1) == Some(last_position) {
+                offset_0
+            } else {
+                return None;
+            };
             last_position = current_position;
-            current_position = position_0;
+            current_position = self.step(current_position, next_offset)?;
         }
-        loop_tiles.insert(current_position);
-        step_count += 1;
+
+        (self.step(starting_position, in_offset) == Some(last_position)).then_some(loop_positions)
     }
 
-    if part_two {
+    /// Follow the loop from the start, returning each tile on it in order, starting with the start
+    /// tile itself.
+    fn find_loop(&self) -> Vec<Position> {
+        let starting_position = self.starting_position;
+        self.trace_loop(&self.tile_map[starting_position.r][starting_position.c])
+            .expect("Start type was checked to close a loop")
+    }
+
+    /// Find the tiles enclosed by the loop.
+    fn interior_tiles(&self, loop_tiles: &HashSet<Position>) -> HashSet<Position> {
         // For each tile not part of the loop, count each time we cross a vertical section of the
         // loop when moving out to the left. If it's odd, it must be an interior tile.
         // Could be more efficient by doing a cumulative sum left to right.
-        let mut interior_count = 0;
-        for r in 0..n_rows {
-            for c in 0..n_cols {
-                if !loop_tiles.contains(&Position { r: r, c: c }) {
+        let mut interior = HashSet::new();
+        for r in 0..self.n_rows() {
+            for c in 0..self.n_cols() {
+                if !loop_tiles.contains(&Position { r, c }) {
                     let mut n = 0;
                     for i in 0..c {
-                        if loop_tiles.contains(&Position { r: r, c: i }) {
-                            match tile_map[r][i] {
-                                TileType::Vertical
-                                | TileType::NorthEastBend
-                                | TileType::NorthWestBend => n += 1,
-                                _ => (),
-                            }
+                        if loop_tiles.contains(&Position { r, c: i })
+                            && self.tile_map[r][i].connects_north()
+                        {
+                            n += 1;
                         }
                     }
                     if n % 2 == 1 {
-                        interior_count += 1;
+                        interior.insert(Position { r, c });
                     }
                 }
             }
         }
-        interior_count
-    } else {
-        if step_count % 2 == 0 {
-            step_count / 2
-        } else {
-            (step_count / 2) + 1
+        interior
+    }
+
+    /// Count the tiles enclosed by the loop, given in order, from its area. Taking each tile
+    /// centre on the loop as a vertex, the shoelace formula gives the area of the polygon, and
+    /// Pick's theorem relates that to the number of whole tiles strictly inside it.
+    fn shoelace_interior_count(loop_positions: &[Position]) -> i64 {
+        let vertices = loop_positions
+            .iter()
+            .map(|p| (p.r as i64, p.c as i64))
+            .collect::<Vec<_>>();
+
+        // Twice the area, which might be negative depending on the orientation of the loop.
+        let double_area = vertices
+            .iter()
+            .zip(vertices.iter().cycle().skip(1))
+            .map(|(a, b)| (a.0 * b.1) - (a.1 * b.0))
+            .sum::<i64>()
+            .abs();
+
+        // Pick's theorem: A = i + b/2 - 1.
+        (double_area - vertices.len() as i64) / 2 + 1
+    }
+
+    /// Count the tiles enclosed by the loop with a single pass along each row, flipping between
+    /// inside and outside whenever we cross a section of the loop which leads north.
+    fn scanline_interior_count(&self, loop_positions: &[Position]) -> i64 {
+        let mut on_loop = vec![vec![false; self.n_cols()]; self.n_rows()];
+        for p in loop_positions {
+            on_loop[p.r][p.c] = true;
+        }
+
+        let mut count = 0;
+        for (tiles, on_loop) in self.tile_map.iter().zip(on_loop) {
+            let mut inside = false;
+            for (tile, on_loop) in tiles.iter().zip(on_loop) {
+                if on_loop {
+                    inside ^= tile.connects_north();
+                } else if inside {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    fn interior_count(&self, method: InteriorMethod) -> i64 {
+        let loop_positions = self.find_loop();
+        match method {
+            InteriorMethod::CrossingCount => {
+                let loop_tiles = HashSet::from_iter(loop_positions);
+                self.interior_tiles(&loop_tiles).len() as i64
+            }
+            InteriorMethod::Shoelace => Self::shoelace_interior_count(&loop_positions),
+            InteriorMethod::Scanline => self.scanline_interior_count(&loop_positions),
+        }
+    }
+
+    /// Draw the maze with box-drawing glyphs. The loop is drawn with heavy lines, interior tiles
+    /// are marked, and the point on the loop farthest from the start is marked with `*`.
+    /// With `colour` set ANSI escapes are used to highlight the loop, dim the pipes that aren't
+    /// on it, and shade the interior, otherwise interior tiles are drawn as `I`.
+    fn render(&self, colour: bool) -> String {
+        const RESET: &str = "\x1b[0m";
+        const LOOP: &str = "\x1b[1;33m";
+        const FARTHEST: &str = "\x1b[1;31m";
+        const INTERIOR: &str = "\x1b[2;42m";
+        const JUNK: &str = "\x1b[2m";
+
+        let loop_positions = self.find_loop();
+        let farthest = loop_positions[loop_positions.len() / 2];
+        let loop_tiles = HashSet::from_iter(loop_positions);
+        let interior = self.interior_tiles(&loop_tiles);
+
+        let mut rendered = String::new();
+        for (r, row) in self.tile_map.iter().enumerate() {
+            for (c, tile) in row.iter().enumerate() {
+                let position = Position { r, c };
+                let (style, glyph) = if position == farthest {
+                    (FARTHEST, '*')
+                } else if position == self.starting_position {
+                    (LOOP, 'S')
+                } else if loop_tiles.contains(&position) {
+                    (LOOP, tile.glyph(true))
+                } else if interior.contains(&position) {
+                    (INTERIOR, if colour { tile.glyph(false) } else { 'I' })
+                } else {
+                    (JUNK, tile.glyph(false))
+                };
+
+                if colour {
+                    rendered += &format!("{style}{glyph}{RESET}");
+                } else {
+                    rendered.push(glyph);
+                }
+            }
+            rendered.push('\n');
         }
+        rendered
+    }
+}
+
+fn compute_answer(
+    puzzle_input: &str,
+    part_two: bool,
+    method: InteriorMethod,
+) -> Result<i64, &'static str> {
+    let maze = PipeMaze::from_str(puzzle_input)?;
+
+    if part_two {
+        return Ok(maze.interior_count(method));
+    }
+
+    let step_count = maze.find_loop().len() as i64;
+    if step_count % 2 == 0 {
+        Ok(step_count / 2)
+    } else {
+        Ok((step_count / 2) + 1)
     }
 }
 
 pub fn run(input_path: PathBuf, part_two: bool) -> Result<i64, Box<dyn Error>> {
+    run_with_method(input_path, part_two, InteriorMethod::Scanline)
+}
+
+pub fn run_with_method(
+    input_path: PathBuf,
+    part_two: bool,
+    method: InteriorMethod,
+) -> Result<i64, Box<dyn Error>> {
     let puzzle_input = fs::read_to_string(input_path)?;
-    let answer = compute_answer(&puzzle_input, part_two);
+    let answer = compute_answer(&puzzle_input, part_two, method)?;
     println!("The answer is {answer}");
     Ok(answer)
 }
+
+/// Count the interior tiles with every method, returning an error if any of them disagree.
+pub fn run_cross_check(input_path: PathBuf) -> Result<i64, Box<dyn Error>> {
+    let puzzle_input = fs::read_to_string(input_path)?;
+    let maze = PipeMaze::from_str(&puzzle_input)?;
+
+    let methods = [
+        InteriorMethod::CrossingCount,
+        InteriorMethod::Shoelace,
+        InteriorMethod::Scanline,
+    ];
+    let counts = methods.map(|m| maze.interior_count(m));
+    for (method, count) in methods.iter().zip(counts) {
+        println!("{method:?}: {count}");
+    }
+
+    if counts.iter().any(|&c| c != counts[0]) {
+        return Err("Interior methods disagree".into());
+    }
+    Ok(counts[0])
+}
+
+/// Print the maze drawn with box-drawing glyphs, optionally using ANSI colours.
+pub fn run_render(input_path: PathBuf, colour: bool) -> Result<String, Box<dyn Error>> {
+    let puzzle_input = fs::read_to_string(input_path)?;
+    let rendered = PipeMaze::from_str(&puzzle_input)?.render(colour);
+    print!("{rendered}");
+    Ok(rendered)
+}
@@ -1,13 +1,44 @@
 use clap::Parser;
 use std::process;
 
-use day10::run;
+use day10::{run_cross_check, run_render, run_with_method, InteriorMethod};
 use util::Cli;
 
+#[derive(Parser)]
+struct Day10Cli {
+    #[command(flatten)]
+    cli: Cli,
+
+    /// How to count the interior tiles in part two: crossing-count, shoelace or scanline.
+    #[arg(long, default_value = "scanline")]
+    interior: InteriorMethod,
+
+    /// Count the interior tiles with every method and check that they agree.
+    #[arg(long)]
+    cross_check: bool,
+
+    /// Draw the maze with box-drawing characters, highlighting the loop and its interior.
+    #[arg(long)]
+    render: bool,
+
+    /// Draw the maze without ANSI colours.
+    #[arg(long, requires = "render")]
+    plain: bool,
+}
+
 fn main() {
-    let cli = Cli::parse();
+    let cli = Day10Cli::parse();
+    let path = cli.cli.puzzle_input_path;
+
+    let result = if cli.render {
+        run_render(path, !cli.plain).map(|_| ())
+    } else if cli.cross_check {
+        run_cross_check(path).map(|_| ())
+    } else {
+        run_with_method(path, cli.cli.part_two, cli.interior).map(|_| ())
+    };
 
-    if let Err(e) = run(cli.puzzle_input_path, cli.part_two) {
+    if let Err(e) = result {
         eprintln!("Error: {e}");
         process::exit(1);
     }
@@ -39,3 +39,74 @@ fn test_part_two_sample_five() {
     let answer = day10::run(input_path, true).unwrap();
     assert_eq!(answer, 10);
 }
+
+#[test]
+fn test_render_plain_sample_one() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input_1.txt");
+    let rendered = day10::run_render(input_path, false).unwrap();
+    assert_eq!(rendered, "─└│┌┐\n┐S━┓│\n└┃I┃│\n─┗━*│\n└│─┘┌\n");
+}
+
+#[test]
+fn test_render_colour_sample_one() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input_1.txt");
+    let rendered = day10::run_render(input_path, true).unwrap();
+    assert!(rendered.starts_with("\x1b[2m─\x1b[0m"));
+    assert!(rendered.contains("\x1b[1;31m*\x1b[0m"));
+    assert!(rendered.contains("\x1b[2;42m┐\x1b[0m"));
+}
+
+#[test]
+fn test_part_two_vertical_start() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/vertical_start.txt");
+    let answer = day10::run(input_path, true).unwrap();
+    assert_eq!(answer, 1);
+}
+
+#[test]
+fn test_part_one_extra_neighbour() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/extra_neighbour.txt");
+    let answer = day10::run(input_path, false).unwrap();
+    assert_eq!(answer, 4);
+}
+
+#[test]
+fn test_ambiguous_start() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/ambiguous_start.txt");
+    let error = day10::run(input_path, false).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Starting tile is ambiguous, more than one loop passes through it"
+    );
+}
+
+#[test]
+fn test_interior_methods_agree() {
+    for file in [
+        "sample_input_1.txt",
+        "sample_input_2.txt",
+        "sample_input_3.txt",
+        "sample_input_4.txt",
+        "sample_input_5.txt",
+        "vertical_start.txt",
+        "extra_neighbour.txt",
+    ] {
+        let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        input_path.push("tests/data");
+        input_path.push(file);
+        assert!(day10::run_cross_check(input_path).is_ok(), "{file}");
+    }
+}
+
+#[test]
+fn test_part_two_sample_five_shoelace() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input_5.txt");
+    let answer = day10::run_with_method(input_path, true, day10::InteriorMethod::Shoelace).unwrap();
+    assert_eq!(answer, 10);
+}
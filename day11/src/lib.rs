@@ -1,67 +1,389 @@
-use itertools::Itertools;
-use std::{error::Error, fs, path::PathBuf};
+use std::{cmp::Ordering, error::Error, fmt, fs, path::PathBuf, str::FromStr};
 
-fn compute_answer(puzzle_input: &str, part_two: bool) -> i64 {
-    let expansion_factor;
-    if part_two {
-        expansion_factor = 1000000i64
-    } else {
-        expansion_factor = 2i64;
+/// The galaxies in an image along with the empty rows and columns which expand.
+pub struct GalaxyMap {
+    galaxies: Vec<(i64, i64)>,
+    row_expansion_indices: Vec<i64>,
+    col_expansion_indices: Vec<i64>,
+}
+
+impl FromStr for GalaxyMap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let image = s
+            .lines()
+            .map(|l| l.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        if let Some(c) = image.iter().flatten().find(|c| **c != '.' && **c != '#') {
+            return Err(format!("Invalid character in image: {c}"));
+        }
+        let n_cols = image.first().map_or(0, |row| row.len());
+        if image.iter().any(|row| row.len() != n_cols) {
+            return Err("Image rows are not all the same length".to_string());
+        }
+
+        let row_expansion_indices = image
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| row.iter().all(|c| *c == '.'))
+            .map(|(row_index, _)| row_index as i64)
+            .collect::<Vec<_>>();
+
+        let col_expansion_indices = (0..n_cols)
+            .filter(|col_index| image.iter().map(|row| &row[*col_index]).all(|c| *c == '.'))
+            .map(|col_index| col_index as i64)
+            .collect::<Vec<_>>();
+
+        let galaxies = image
+            .iter()
+            .enumerate()
+            .flat_map(|(row_index, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, g)| **g == '#')
+                    .map(move |(col_index, _)| (row_index as i64, col_index as i64))
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Self {
+            galaxies,
+            row_expansion_indices,
+            col_expansion_indices,
+        })
     }
+}
 
-    let image = puzzle_input
-        .lines()
-        .map(|l| l.chars().collect::<Vec<_>>())
-        .collect::<Vec<_>>();
+/// How to measure the distance between two galaxies.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Metric {
+    /// Moving one step up, down, left or right at a time, as in the puzzle.
+    Manhattan,
+    /// Also allowing diagonal steps.
+    Chebyshev,
+    /// In a straight line.
+    Euclidean,
+}
 
-    let row_expansion_indices = image
-        .iter()
-        .enumerate()
-        .filter(|(_, row)| row.iter().all(|c| *c == '.'))
-        .map(|(row_index, _)| row_index as i64)
-        .collect::<Vec<_>>();
+impl FromStr for Metric {
+    type Err = String;
 
-    let col_expansion_indices = (0..image[0].len())
-        .filter(|col_index| image.iter().map(|row| &row[*col_index]).all(|c| *c == '.'))
-        .map(|col_index| col_index as i64)
-        .collect::<Vec<_>>();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "manhattan" => Ok(Self::Manhattan),
+            "chebyshev" => Ok(Self::Chebyshev),
+            "euclidean" => Ok(Self::Euclidean),
+            _ => Err(format!(
+                "Unknown metric {s}, expected manhattan, chebyshev or euclidean"
+            )),
+        }
+    }
+}
 
-    let galaxies = image.iter().enumerate().flat_map(|(row_index, row)| {
-        row.iter()
+/// The error for distances which don't fit in an `i64`.
+fn too_far() -> String {
+    "Distances are too large to measure at this expansion factor".to_string()
+}
+
+impl Metric {
+    /// Distance between two non-negative positions, or an error if it's too large for an `i64`.
+    pub fn distance(&self, a: (i64, i64), b: (i64, i64)) -> Result<Distance, String> {
+        let (dr, dc) = ((a.0 - b.0).abs(), (a.1 - b.1).abs());
+        Ok(match self {
+            Self::Manhattan => Distance::Steps(dr.checked_add(dc).ok_or_else(too_far)?),
+            Self::Chebyshev => Distance::Steps(dr.max(dc)),
+            Self::Euclidean => Distance::Straight((dr as f64).hypot(dc as f64)),
+        })
+    }
+}
+
+/// A distance between galaxies. Metrics which only take whole steps stay exact as integers,
+/// since an `f64` can't represent every distance past 2^53 at large expansion factors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Distance {
+    Steps(i64),
+    Straight(f64),
+}
+
+impl Distance {
+    pub fn as_f64(&self) -> f64 {
+        match *self {
+            Self::Steps(d) => d as f64,
+            Self::Straight(d) => d,
+        }
+    }
+
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Steps(a), Self::Steps(b)) => a.cmp(b),
+            _ => self.as_f64().total_cmp(&other.as_f64()),
+        }
+    }
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        match (self, other) {
+            (Self::Steps(a), Self::Steps(b)) => a.checked_add(b).map(Self::Steps),
+            _ => Some(Self::Straight(self.as_f64() + other.as_f64())),
+        }
+    }
+}
+
+impl fmt::Display for Distance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Steps(d) => write!(f, "{d}"),
+            Self::Straight(d) => write!(f, "{d}"),
+        }
+    }
+}
+
+/// Number of another galaxy and the distance to it.
+pub type Neighbour = (usize, Distance);
+
+/// Sum of the distances between every pair of values, by sorting them so that each value is
+/// subtracted from all the larger ones at once. Returns `None` if the sum overflows.
+fn pairwise_distance_sum(mut values: Vec<i64>) -> Option<i64> {
+    values.sort_unstable();
+
+    // Work in i128 so that the running sums can't overflow, then check the total fits.
+    let mut prefix_sum = 0i128;
+    let mut total = 0i128;
+    for (i, v) in values.into_iter().enumerate() {
+        total += v as i128 * i as i128 - prefix_sum;
+        prefix_sum += v as i128;
+    }
+    i64::try_from(total).ok()
+}
+
+impl GalaxyMap {
+    pub fn len(&self) -> usize {
+        self.galaxies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.galaxies.is_empty()
+    }
+
+    /// Position of every galaxy after each empty row and column has been replaced by
+    /// `expansion_factor` of them, or an error if a position is too large for an `i64`.
+    pub fn expanded(&self, expansion_factor: i64) -> Result<Vec<(i64, i64)>, String> {
+        // Each coordinate moves along by the number of empty rows/columns before it.
+        let expand = |x: i64, expansion_indices: &[i64]| {
+            (expansion_indices.partition_point(|i| *i < x) as i64)
+                .checked_mul(expansion_factor - 1)
+                .and_then(|shift| x.checked_add(shift))
+                .ok_or_else(too_far)
+        };
+
+        self.galaxies
+            .iter()
+            .map(|&(r, c)| {
+                Ok((
+                    expand(r, &self.row_expansion_indices)?,
+                    expand(c, &self.col_expansion_indices)?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Sum of the shortest path lengths between every pair of galaxies. The distance splits into
+    /// independent row and column parts, so each axis is summed separately.
+    pub fn distance_sum(&self, expansion_factor: i64) -> Result<i64, String> {
+        let (rows, cols) = self.expanded(expansion_factor)?.into_iter().unzip();
+        pairwise_distance_sum(rows)
+            .zip(pairwise_distance_sum(cols))
+            .and_then(|(rows, cols)| rows.checked_add(cols))
+            .ok_or_else(too_far)
+    }
+
+    /// Distance between two galaxies, numbered from 1 in reading order as in the puzzle.
+    pub fn distance(
+        &self,
+        a: usize,
+        b: usize,
+        expansion_factor: i64,
+        metric: Metric,
+    ) -> Result<Distance, String> {
+        let expanded = self.expanded(expansion_factor)?;
+        let galaxy = |n: usize| {
+            n.checked_sub(1)
+                .and_then(|i| expanded.get(i))
+                .ok_or(format!(
+                    "No galaxy {n}, galaxies are numbered 1 to {}",
+                    self.len()
+                ))
+        };
+        let (a, b) = (galaxy(a)?, galaxy(b)?);
+        metric.distance(*a, *b)
+    }
+
+    /// The closest other galaxy to each galaxy, along with the distance to it. Galaxies are
+    /// numbered from 1, and ties go to the lowest numbered galaxy.
+    pub fn nearest_neighbours(
+        &self,
+        expansion_factor: i64,
+        metric: Metric,
+    ) -> Result<Vec<Option<Neighbour>>, String> {
+        let expanded = self.expanded(expansion_factor)?;
+        expanded
+            .iter()
             .enumerate()
-            .filter(|(_, g)| **g == '#')
-            .map(move |(col_index, _)| (row_index as i64, col_index as i64))
-    });
-
-    galaxies
-        .combinations(2)
-        .map(|g| {
-            // Find the number of intervening rows/columns to be expanded between each pair.
-            let row_min = g[0].0.min(g[1].0);
-            let row_max = g[0].0.max(g[1].0);
-            let col_min = g[0].1.min(g[1].1);
-            let col_max = g[0].1.max(g[1].1);
-
-            let row_dist = (row_max - row_min)
-                + row_expansion_indices
-                    .iter()
-                    .filter(|r| **r > row_min && **r < row_max)
-                    .count() as i64
-                    * (expansion_factor - 1);
-            let col_dist = (col_max - col_min)
-                + col_expansion_indices
+            .map(|(i, &a)| {
+                let distances = expanded
                     .iter()
-                    .filter(|c| **c > col_min && **c < col_max)
-                    .count() as i64
-                    * (expansion_factor - 1);
-            row_dist + col_dist
-        })
-        .sum()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .map(|(j, &b)| Ok((j + 1, metric.distance(a, b)?)))
+                    .collect::<Result<Vec<_>, String>>()?;
+                Ok(distances.into_iter().min_by(|x, y| x.1.total_cmp(&y.1)))
+            })
+            .collect()
+    }
+
+    /// Total length of the shortest set of links which joins up every galaxy, found with Prim's
+    /// algorithm over the complete graph between them.
+    pub fn minimum_spanning_tree_cost(
+        &self,
+        expansion_factor: i64,
+        metric: Metric,
+    ) -> Result<Distance, String> {
+        let expanded = self.expanded(expansion_factor)?;
+        // A zero distance of the right kind for the metric.
+        let zero = metric.distance((0, 0), (0, 0))?;
+        if expanded.is_empty() {
+            return Ok(zero);
+        }
+
+        // Distance from each galaxy not yet in the tree to the closest one that is, if any.
+        let mut in_tree = vec![false; expanded.len()];
+        let mut closest: Vec<Option<Distance>> = vec![None; expanded.len()];
+        closest[0] = Some(zero);
+
+        let mut cost = zero;
+        for _ in 0..expanded.len() {
+            let (next, distance) = closest
+                .iter()
+                .enumerate()
+                .filter_map(|(i, d)| d.filter(|_| !in_tree[i]).map(|d| (i, d)))
+                .min_by(|x, y| x.1.total_cmp(&y.1))
+                .unwrap();
+            in_tree[next] = true;
+            cost = cost.checked_add(distance).ok_or_else(too_far)?;
+
+            for (i, &galaxy) in expanded.iter().enumerate() {
+                if !in_tree[i] {
+                    let distance = metric.distance(expanded[next], galaxy)?;
+                    if closest[i].is_none_or(|d| distance.total_cmp(&d).is_lt()) {
+                        closest[i] = Some(distance);
+                    }
+                }
+            }
+        }
+        Ok(cost)
+    }
+}
+
+/// The expansion factor given by the puzzle for each part.
+pub fn default_expansion_factor(part_two: bool) -> i64 {
+    if part_two {
+        1000000
+    } else {
+        2
+    }
+}
+
+/// Each empty row and column must be replaced by at least one, or distances lose their meaning.
+fn check_expansion_factor(expansion_factor: i64) -> Result<(), String> {
+    if expansion_factor < 1 {
+        return Err(format!(
+            "Expansion factor must be at least 1, not {expansion_factor}"
+        ));
+    }
+    Ok(())
+}
+
+fn compute_answer(puzzle_input: &str, expansion_factor: i64) -> Result<i64, String> {
+    check_expansion_factor(expansion_factor)?;
+    GalaxyMap::from_str(puzzle_input)?.distance_sum(expansion_factor)
 }
 
 pub fn run(input_path: PathBuf, part_two: bool) -> Result<i64, Box<dyn Error>> {
+    run_with_expansion(input_path, default_expansion_factor(part_two))
+}
+
+pub fn run_with_expansion(
+    input_path: PathBuf,
+    expansion_factor: i64,
+) -> Result<i64, Box<dyn Error>> {
     let puzzle_input = fs::read_to_string(input_path)?;
-    let answer = compute_answer(&puzzle_input, part_two);
+    let answer = compute_answer(&puzzle_input, expansion_factor)?;
     println!("The answer is {answer}");
     Ok(answer)
 }
+
+/// Print the distance between two galaxies, numbered from 1.
+pub fn run_distance(
+    input_path: PathBuf,
+    expansion_factor: i64,
+    metric: Metric,
+    a: usize,
+    b: usize,
+) -> Result<Distance, Box<dyn Error>> {
+    check_expansion_factor(expansion_factor)?;
+    let puzzle_input = fs::read_to_string(input_path)?;
+    let distance = GalaxyMap::from_str(&puzzle_input)?.distance(a, b, expansion_factor, metric)?;
+    println!("The distance between galaxies {a} and {b} is {distance}");
+    Ok(distance)
+}
+
+/// Print the nearest neighbour of every galaxy.
+pub fn run_nearest(
+    input_path: PathBuf,
+    expansion_factor: i64,
+    metric: Metric,
+) -> Result<Vec<Option<Neighbour>>, Box<dyn Error>> {
+    check_expansion_factor(expansion_factor)?;
+    let puzzle_input = fs::read_to_string(input_path)?;
+    let neighbours =
+        GalaxyMap::from_str(&puzzle_input)?.nearest_neighbours(expansion_factor, metric)?;
+    for (i, neighbour) in neighbours.iter().enumerate() {
+        match neighbour {
+            Some((j, distance)) => println!("Galaxy {}: nearest is {j} at {distance}", i + 1),
+            None => println!("Galaxy {}: no other galaxies", i + 1),
+        }
+    }
+    Ok(neighbours)
+}
+
+/// Print the cost of the minimum spanning tree joining every galaxy.
+pub fn run_spanning_tree(
+    input_path: PathBuf,
+    expansion_factor: i64,
+    metric: Metric,
+) -> Result<Distance, Box<dyn Error>> {
+    check_expansion_factor(expansion_factor)?;
+    let puzzle_input = fs::read_to_string(input_path)?;
+    let cost =
+        GalaxyMap::from_str(&puzzle_input)?.minimum_spanning_tree_cost(expansion_factor, metric)?;
+    println!("The minimum spanning tree cost is {cost}");
+    Ok(cost)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itertools::Itertools;
+
+    #[test]
+    fn test_pairwise_distance_sum() {
+        let values = vec![7i64, -3, 12, 0, 7, 5, 100];
+        let brute_force = values
+            .iter()
+            .combinations(2)
+            .map(|p| (p[0] - p[1]).abs())
+            .sum::<i64>();
+        assert_eq!(pairwise_distance_sum(values), Some(brute_force));
+        assert_eq!(pairwise_distance_sum(vec![0, i64::MAX, i64::MAX]), None);
+    }
+}
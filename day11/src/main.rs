@@ -1,13 +1,57 @@
 use clap::Parser;
 use std::process;
 
-use day11::run;
+use day11::{
+    default_expansion_factor, run_distance, run_nearest, run_spanning_tree, run_with_expansion,
+    Metric,
+};
 use util::Cli;
 
+#[derive(Parser)]
+struct Day11Cli {
+    #[command(flatten)]
+    cli: Cli,
+
+    /// Replace each empty row and column with this many, instead of the factor for the part.
+    #[arg(long, value_parser = clap::value_parser!(i64).range(1..))]
+    expansion: Option<i64>,
+
+    /// Print the distance between these two galaxies instead, numbered from 1 in reading order.
+    #[arg(long, num_args = 2, value_names = ["A", "B"])]
+    between: Option<Vec<usize>>,
+
+    /// How to measure distances for --between, --nearest and --spanning-tree: manhattan,
+    /// chebyshev or euclidean.
+    #[arg(long, default_value = "manhattan")]
+    metric: Metric,
+
+    /// Print the nearest neighbour of every galaxy instead.
+    #[arg(long)]
+    nearest: bool,
+
+    /// Print the cost of the minimum spanning tree joining every galaxy instead.
+    #[arg(long)]
+    spanning_tree: bool,
+}
+
 fn main() {
-    let cli = Cli::parse();
+    let cli = Day11Cli::parse();
+    let path = cli.cli.puzzle_input_path;
+    let expansion_factor = cli
+        .expansion
+        .unwrap_or(default_expansion_factor(cli.cli.part_two));
+
+    let result = if let Some(between) = cli.between {
+        run_distance(path, expansion_factor, cli.metric, between[0], between[1]).map(|_| ())
+    } else if cli.nearest {
+        run_nearest(path, expansion_factor, cli.metric).map(|_| ())
+    } else if cli.spanning_tree {
+        run_spanning_tree(path, expansion_factor, cli.metric).map(|_| ())
+    } else {
+        run_with_expansion(path, expansion_factor).map(|_| ())
+    };
 
-    if let Err(e) = run(cli.puzzle_input_path, cli.part_two) {
+    if let Err(e) = result {
         eprintln!("Error: {e}");
         process::exit(1);
     }
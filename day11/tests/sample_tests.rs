@@ -1,5 +1,7 @@
 use std::path::PathBuf;
 
+use day11::{Distance, Metric};
+
 #[test]
 fn test_part_one() {
     let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -17,3 +19,100 @@ fn test_part_two() {
     let answer = day11::run(input_path, true).unwrap();
     assert_eq!(answer, 82000210);
 }
+
+#[test]
+fn test_expansion_factors() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let answer = day11::run_with_expansion(input_path.clone(), 10).unwrap();
+    assert_eq!(answer, 1030);
+    let answer = day11::run_with_expansion(input_path, 100).unwrap();
+    assert_eq!(answer, 8410);
+}
+
+#[test]
+fn test_distance_between_galaxies() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    for (a, b, distance) in [(5, 9, 9), (1, 7, 15), (3, 6, 17), (8, 9, 5)] {
+        let answer = day11::run_distance(input_path.clone(), 2, Metric::Manhattan, a, b).unwrap();
+        assert_eq!(answer, Distance::Steps(distance));
+    }
+    assert!(day11::run_distance(input_path, 2, Metric::Manhattan, 0, 10).is_err());
+}
+
+#[test]
+fn test_metrics() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let chebyshev = day11::run_distance(input_path.clone(), 2, Metric::Chebyshev, 1, 7).unwrap();
+    assert_eq!(chebyshev, Distance::Steps(10));
+    let euclidean = day11::run_distance(input_path, 2, Metric::Euclidean, 1, 7).unwrap();
+    assert_eq!(euclidean, Distance::Straight(125f64.sqrt()));
+}
+
+#[test]
+fn test_nearest_neighbours() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let neighbours = day11::run_nearest(input_path, 2, Metric::Manhattan).unwrap();
+    let neighbours = neighbours
+        .into_iter()
+        .map(|n| n.unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        neighbours,
+        vec![
+            (2, Distance::Steps(6)),
+            (4, Distance::Steps(5)),
+            (5, Distance::Steps(5)),
+            (2, Distance::Steps(5)),
+            (3, Distance::Steps(5)),
+            (4, Distance::Steps(6)),
+            (9, Distance::Steps(5)),
+            (9, Distance::Steps(5)),
+            (7, Distance::Steps(5))
+        ]
+    );
+}
+
+#[test]
+fn test_spanning_tree() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let manhattan = day11::run_spanning_tree(input_path.clone(), 2, Metric::Manhattan).unwrap();
+    assert_eq!(manhattan, Distance::Steps(44));
+    let chebyshev = day11::run_spanning_tree(input_path.clone(), 2, Metric::Chebyshev).unwrap();
+    assert_eq!(chebyshev, Distance::Steps(33));
+    let euclidean = day11::run_spanning_tree(input_path, 2, Metric::Euclidean).unwrap();
+    assert!((euclidean.as_f64() - 35.65524898756421).abs() < 1e-9);
+}
+
+#[test]
+fn test_invalid_expansion_factor() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    assert!(day11::run_with_expansion(input_path.clone(), 0).is_err());
+    assert!(day11::run_spanning_tree(input_path, -3, day11::Metric::Manhattan).is_err());
+}
+
+#[test]
+fn test_exact_distance_at_large_expansion() {
+    // Past 2^53 an f64 would round this to an even number.
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let expansion_factor = (1 << 53) + 1;
+    let answer = day11::run_distance(input_path, expansion_factor, Metric::Manhattan, 1, 2);
+    assert_eq!(answer.unwrap(), Distance::Steps(expansion_factor + 4));
+}
+
+#[test]
+fn test_expansion_overflow() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let expansion_factor = 1000000000000000000;
+    assert!(day11::run_with_expansion(input_path.clone(), expansion_factor).is_err());
+    assert!(day11::run_distance(input_path.clone(), i64::MAX, Metric::Chebyshev, 1, 2).is_err());
+    let answer = day11::run_spanning_tree(input_path, 2 * expansion_factor, Metric::Manhattan);
+    assert!(answer.is_err());
+}
@@ -1,123 +1,750 @@
-use cached::proc_macro::cached;
-use std::{error::Error, fs, path::PathBuf};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::prelude::*;
+use std::{error::Error, fs, path::PathBuf, str::FromStr};
 
-#[cached]
-fn solve(springs: Vec<char>, current_group: i64, remaining_groups: Vec<i64>) -> i64 {
-    let next_spring = springs[0];
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Spring {
+    Operational,
+    Damaged,
+    Unknown,
+}
 
-    if next_spring == '?' {
-        // Branch
-        let mut s_hash = vec!['#'];
-        s_hash.append(&mut springs[1..].to_vec().clone());
-        let case_hash = solve(s_hash, current_group, remaining_groups.clone());
+impl TryFrom<char> for Spring {
+    type Error = String;
 
-        let mut s_dot = vec!['.'];
-        s_dot.append(&mut springs[1..].to_vec().clone());
-        let case_dot = solve(s_dot, current_group, remaining_groups);
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c {
+            '.' => Ok(Self::Operational),
+            '#' => Ok(Self::Damaged),
+            '?' => Ok(Self::Unknown),
+            _ => Err(format!("Invalid spring: {c}")),
+        }
+    }
+}
 
-        return case_dot + case_hash;
-    } else {
-        if springs.len() == 1 {
-            // Base case
-            if remaining_groups.len() == 0 {
-                match next_spring {
-                    '.' if current_group == 0 => 1,
-                    '#' if current_group == 1 => 1,
-                    _ => 0,
+impl From<Spring> for char {
+    fn from(spring: Spring) -> Self {
+        match spring {
+            Spring::Operational => '.',
+            Spring::Damaged => '#',
+            Spring::Unknown => '?',
+        }
+    }
+}
+
+fn springs_to_string(springs: &[Spring]) -> String {
+    springs.iter().map(|&s| char::from(s)).collect()
+}
+
+impl Spring {
+    fn from_damaged(damaged: bool) -> Self {
+        if damaged {
+            Self::Damaged
+        } else {
+            Self::Operational
+        }
+    }
+
+    fn can_be(&self, damaged: bool) -> bool {
+        match self {
+            Self::Operational => !damaged,
+            Self::Damaged => damaged,
+            Self::Unknown => true,
+        }
+    }
+}
+
+/// One row of the condition records: the springs, some of which are unknown, and the sizes of
+/// each contiguous group of damaged springs in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpringRow {
+    pub springs: Vec<Spring>,
+    pub groups: Vec<usize>,
+}
+
+impl FromStr for SpringRow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (spring_str, count_str) = s
+            .split_once(" ")
+            .ok_or(format!("Missing group sizes: {s}"))?;
+        let springs = spring_str
+            .chars()
+            .map(Spring::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        let groups = count_str
+            .split(",")
+            .map(|s| match s.parse::<usize>() {
+                Ok(0) | Err(_) => Err(format!("Invalid group size: {s}")),
+                Ok(n) => Ok(n),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { springs, groups })
+    }
+}
+
+impl SpringRow {
+    /// Repeat the row `factor` times, joining the copies of the springs with unknowns.
+    pub fn unfold(&self, factor: usize) -> Result<Self, String> {
+        if factor == 0 {
+            return Err("Unfold factor must be at least 1".to_string());
+        }
+        Ok(Self {
+            springs: vec![self.springs.clone(); factor].join(&Spring::Unknown),
+            groups: self.groups.repeat(factor),
+        })
+    }
+
+    pub fn count_arrangements(&self) -> Result<u64, String> {
+        self.arrangement_counts().total()
+    }
+
+    /// Every valid arrangement of the row, generated lazily in lexicographic order, where `#`
+    /// sorts before `.`.
+    pub fn arrangements(&self) -> Arrangements<'_> {
+        Arrangements {
+            counts: self.arrangement_counts(),
+            path: vec![],
+            started: false,
+        }
+    }
+
+    /// Tabulate the number of ways to finish the row from every state.
+    pub fn arrangement_counts(&self) -> ArrangementCounts<'_> {
+        let mut counts = ArrangementCounts {
+            row: self,
+            max_run: self.groups.iter().copied().max().unwrap_or(0),
+            ways: vec![],
+        };
+        counts.ways = vec![0; (self.springs.len() + 1) * counts.n_states()];
+
+        // A finished row is valid if every group has been closed off, or the last one is still
+        // open but complete.
+        let n_groups = self.groups.len();
+        let end = self.springs.len();
+        *counts.get_mut(end, n_groups, 0) = 1;
+        if let Some(&last) = self.groups.last() {
+            *counts.get_mut(end, n_groups - 1, last) = 1;
+        }
+
+        // Fill in backwards from the end of the row, so each state can add up the ways from the
+        // states it leads to.
+        for position in (0..end).rev() {
+            for group in 0..=n_groups {
+                for run in 0..=counts.max_run {
+                    let ways = [false, true]
+                        .into_iter()
+                        .filter_map(|damaged| counts.step(position, group, run, damaged))
+                        .map(|(group, run)| counts.ways(position + 1, group, run))
+                        .fold(0, u64::saturating_add);
+                    *counts.get_mut(position, group, run) = ways;
                 }
-            } else if remaining_groups.len() == 1 && current_group == 0 {
-                solve(springs, remaining_groups[0], remaining_groups[1..].to_vec())
-            } else {
-                0
             }
+        }
+        counts
+    }
+}
+
+/// The number of ways to finish a row of springs from each state part way along it. A state is
+/// made up of the position of the next spring, the number of groups already finished, and the
+/// length of the run of damaged springs so far in the current group.
+///
+/// Counts which don't fit in a `u64` saturate at [`TOO_MANY`], which still tells apart the states
+/// that lead to arrangements from those that don't.
+pub struct ArrangementCounts<'a> {
+    row: &'a SpringRow,
+    max_run: usize,
+    ways: Vec<u64>,
+}
+
+/// Stands in for any number of arrangements too large to count in a `u64`.
+pub const TOO_MANY: u64 = u64::MAX;
+
+impl ArrangementCounts<'_> {
+    fn n_states(&self) -> usize {
+        (self.row.groups.len() + 1) * (self.max_run + 1)
+    }
+
+    fn index(&self, position: usize, group: usize, run: usize) -> usize {
+        (position * (self.row.groups.len() + 1) + group) * (self.max_run + 1) + run
+    }
+
+    fn get_mut(&mut self, position: usize, group: usize, run: usize) -> &mut u64 {
+        let index = self.index(position, group, run);
+        &mut self.ways[index]
+    }
+
+    pub fn ways(&self, position: usize, group: usize, run: usize) -> u64 {
+        self.ways[self.index(position, group, run)]
+    }
+
+    /// The number of arrangements of the whole row, or an error if there are too many to count.
+    pub fn total(&self) -> Result<u64, String> {
+        match self.ways(0, 0, 0) {
+            TOO_MANY => Err("Row has too many arrangements to count".to_string()),
+            total => Ok(total),
+        }
+    }
+
+    /// The group and run after setting the spring at `position` to damaged or not, or `None` if
+    /// that spring can't be set that way or it breaks the group sizes.
+    fn step(
+        &self,
+        position: usize,
+        group: usize,
+        run: usize,
+        damaged: bool,
+    ) -> Option<(usize, usize)> {
+        if !self.row.springs[position].can_be(damaged) {
+            return None;
+        }
+
+        let groups = &self.row.groups;
+        if damaged {
+            // Extend the current group, or start the next one.
+            (group < groups.len() && run < groups[group]).then_some((group, run + 1))
+        } else if run == 0 {
+            Some((group, 0))
         } else {
-            if current_group == 0 {
-                match next_spring {
-                    '.' => solve(springs[1..].to_vec(), 0, remaining_groups),
-                    '#' if remaining_groups.len() == 0 => 0,
-                    '#' if remaining_groups.len() > 0 => {
-                        // Start a new group if possible
-                        solve(springs, remaining_groups[0], remaining_groups[1..].to_vec())
+            // Close off the current group, which must be complete.
+            (groups.get(group) == Some(&run)).then_some((group + 1, 0))
+        }
+    }
+
+    /// The state after setting the spring at `position`, if it still leads to any arrangements.
+    fn viable_step(
+        &self,
+        position: usize,
+        group: usize,
+        run: usize,
+        damaged: bool,
+    ) -> Option<(usize, usize)> {
+        self.step(position, group, run, damaged)
+            .filter(|&(group, run)| self.ways(position + 1, group, run) > 0)
+    }
+
+    /// Pick an arrangement uniformly at random, or `None` if there aren't any. Each spring is set
+    /// damaged with probability in proportion to the number of arrangements that follow from it,
+    /// so the choice is only uniform if [`total`](Self::total) succeeds.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<Vec<Spring>> {
+        let (mut group, mut run) = (0, 0);
+        let total = self.ways(0, group, run);
+        if total == 0 {
+            return None;
+        }
+
+        let mut springs = Vec::with_capacity(self.row.springs.len());
+        for position in 0..self.row.springs.len() {
+            let damaged_ways = self
+                .step(position, group, run, true)
+                .map_or(0, |(group, run)| self.ways(position + 1, group, run));
+            let damaged = rng.gen_range(0..self.ways(position, group, run)) < damaged_ways;
+            (group, run) = self.step(position, group, run, damaged).unwrap();
+            springs.push(Spring::from_damaged(damaged));
+        }
+        Some(springs)
+    }
+
+    /// The row with every spring that is the same in all arrangements filled in, leaving the rest
+    /// unknown, or `None` if there are no arrangements.
+    pub fn forced_springs(&self) -> Option<Vec<Spring>> {
+        let n_springs = self.row.springs.len();
+        if self.ways(0, 0, 0) == 0 {
+            return None;
+        }
+
+        // Work forwards through the states which can be reached from the start and still lead to
+        // an arrangement, recording which way each spring can be set along the way.
+        let mut reachable = vec![false; self.n_states()];
+        reachable[self.index(0, 0, 0)] = true;
+        let mut possible = vec![[false; 2]; n_springs];
+        for (position, possible) in possible.iter_mut().enumerate() {
+            let mut next_reachable = vec![false; self.n_states()];
+            for group in 0..=self.row.groups.len() {
+                for run in 0..=self.max_run {
+                    if !reachable[self.index(0, group, run)] {
+                        continue;
                     }
-                    _ => 0,
-                }
-            } else if current_group == 1 {
-                // Current group must be closed.
-                let second_spring = springs[1];
-                match (next_spring, second_spring) {
-                    ('#', '.') => solve(springs[1..].to_vec(), 0, remaining_groups),
-                    ('#', '?') => {
-                        let mut s = vec!['.'];
-                        s.append(&mut springs[2..].to_vec().clone());
-                        solve(s, 0, remaining_groups)
+                    for damaged in [false, true] {
+                        if let Some((group, run)) = self.viable_step(position, group, run, damaged)
+                        {
+                            possible[damaged as usize] = true;
+                            next_reachable[self.index(0, group, run)] = true;
+                        }
                     }
-                    _ => 0,
-                }
-            } else {
-                match next_spring {
-                    // Current group must be extended.
-                    '#' => solve(springs[1..].to_vec(), current_group - 1, remaining_groups),
-                    _ => 0,
                 }
             }
+            reachable = next_reachable;
         }
+
+        Some(
+            possible
+                .into_iter()
+                .map(|p| match p {
+                    [true, false] => Spring::Operational,
+                    [false, true] => Spring::Damaged,
+                    _ => Spring::Unknown,
+                })
+                .collect(),
+        )
     }
 }
 
-fn parse_line(line: &str, part_two: bool) -> i64 {
-    let (spring_str, count_str) = line.split_once(" ").unwrap();
-    let mut counts = count_str
-        .split(",")
-        .map(|s| s.parse::<i64>().unwrap())
-        .collect::<Vec<_>>();
-    let mut springs = spring_str.chars().collect::<Vec<_>>();
+/// Lazy iterator over the arrangements of a row, in lexicographic order.
+pub struct Arrangements<'a> {
+    counts: ArrangementCounts<'a>,
+    /// The group and run before each spring so far, along with whether it was set damaged.
+    path: Vec<(usize, usize, bool)>,
+    started: bool,
+}
 
-    if part_two {
-        springs = vec![springs; 5].join(&'?');
-        counts = vec![counts; 5].concat();
+impl Arrangements<'_> {
+    /// Extend the path to the end of the row, taking the first viable choice for each spring.
+    fn descend(&mut self, mut group: usize, mut run: usize) {
+        for position in self.path.len()..self.counts.row.springs.len() {
+            let damaged = self
+                .counts
+                .viable_step(position, group, run, true)
+                .is_some();
+            self.path.push((group, run, damaged));
+            (group, run) = self
+                .counts
+                .viable_step(position, group, run, damaged)
+                .unwrap();
+        }
     }
 
-    solve(springs, 0, counts) as i64
+    fn current(&self) -> Vec<Spring> {
+        self.path
+            .iter()
+            .map(|&(_, _, damaged)| Spring::from_damaged(damaged))
+            .collect()
+    }
 }
-fn compute_answer(puzzle_input: &str, part_two: bool) -> i64 {
-    puzzle_input.lines().map(|l| parse_line(l, part_two)).sum()
+
+impl Iterator for Arrangements<'_> {
+    type Item = Vec<Spring>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            if self.counts.ways(0, 0, 0) == 0 {
+                return None;
+            }
+            self.descend(0, 0);
+            return Some(self.current());
+        }
+
+        // Backtrack to the last damaged spring which could have been operational instead, then
+        // take the first arrangement after it.
+        loop {
+            let (group, run, damaged) = self.path.pop()?;
+            let position = self.path.len();
+            if let Some((next_group, next_run)) = damaged
+                .then(|| self.counts.viable_step(position, group, run, false))
+                .flatten()
+            {
+                self.path.push((group, run, false));
+                self.descend(next_group, next_run);
+                return Some(self.current());
+            }
+        }
+    }
 }
 
-pub fn run(input_path: PathBuf, part_two: bool) -> Result<i64, Box<dyn Error>> {
+fn compute_answer(puzzle_input: &str, unfold_factor: usize) -> Result<u64, String> {
+    let counts = puzzle_input
+        .par_lines()
+        .map(|l| {
+            SpringRow::from_str(l)?
+                .unfold(unfold_factor)?
+                .count_arrangements()
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    counts
+        .into_iter()
+        .try_fold(0u64, |total, c| total.checked_add(c))
+        .ok_or_else(|| "Total number of arrangements is too large to count".to_string())
+}
+
+/// The unfold factor given by the puzzle for each part.
+pub fn default_unfold_factor(part_two: bool) -> usize {
+    if part_two {
+        5
+    } else {
+        1
+    }
+}
+
+pub fn run(input_path: PathBuf, part_two: bool) -> Result<u64, Box<dyn Error>> {
+    run_with_unfold(input_path, default_unfold_factor(part_two))
+}
+
+/// Solve with each row repeated `unfold_factor` times.
+pub fn run_with_unfold(input_path: PathBuf, unfold_factor: usize) -> Result<u64, Box<dyn Error>> {
     let puzzle_input = fs::read_to_string(input_path)?;
-    let answer = compute_answer(&puzzle_input, part_two);
+    let answer = compute_answer(&puzzle_input, unfold_factor)?;
     println!("The answer is {answer}");
     Ok(answer)
 }
 
+/// A nonogram puzzle: the group sizes for each row and column of a grid, to be filled in with
+/// damaged springs.
+pub struct Nonogram {
+    rows: Vec<Vec<usize>>,
+    columns: Vec<Vec<usize>>,
+}
+
+type Grid = Vec<Vec<Spring>>;
+
+impl FromStr for Nonogram {
+    type Err = String;
+
+    /// Parse a `rows` section then a `columns` section, with the group sizes for each line given
+    /// comma separated as in the condition records, or `0` for an empty line.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (rows_str, columns_str) = s
+            .trim()
+            .split_once("\n\n")
+            .ok_or("Expected rows and columns sections separated by a blank line")?;
+
+        let parse_section = |section: &str, name: &str| {
+            // Ignore any extra blank lines around the section.
+            let mut lines = section.trim().lines();
+            if lines.next().map(str::trim) != Some(name) {
+                return Err(format!("Expected section to start with {name}"));
+            }
+            lines
+                .map(|l| match l.trim() {
+                    "0" => Ok(vec![]),
+                    l => l
+                        .split(",")
+                        .map(|s| match s.parse::<usize>() {
+                            Ok(0) | Err(_) => Err(format!("Invalid group size: {s}")),
+                            Ok(n) => Ok(n),
+                        })
+                        .collect(),
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        Ok(Self {
+            rows: parse_section(rows_str, "rows")?,
+            columns: parse_section(columns_str, "columns")?,
+        })
+    }
+}
+
+impl Nonogram {
+    /// Fill in every cell that is forced by its row or column, repeating until nothing changes.
+    /// Returns `false` if some line has no arrangements left.
+    fn propagate(&self, grid: &mut Grid) -> bool {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (r, groups) in self.rows.iter().enumerate() {
+                let row = SpringRow {
+                    springs: grid[r].clone(),
+                    groups: groups.clone(),
+                };
+                let Some(forced) = row.arrangement_counts().forced_springs() else {
+                    return false;
+                };
+                changed |= forced != grid[r];
+                grid[r] = forced;
+            }
+
+            for (c, groups) in self.columns.iter().enumerate() {
+                let column = SpringRow {
+                    springs: grid.iter().map(|row| row[c]).collect(),
+                    groups: groups.clone(),
+                };
+                let Some(forced) = column.arrangement_counts().forced_springs() else {
+                    return false;
+                };
+                for (row, spring) in grid.iter_mut().zip(forced) {
+                    changed |= row[c] != spring;
+                    row[c] = spring;
+                }
+            }
+        }
+        true
+    }
+
+    /// Propagate, then guess the first unknown cell both ways when stuck, stopping once `limit`
+    /// solutions have been found.
+    fn search(&self, mut grid: Grid, solutions: &mut Vec<Grid>, limit: usize) {
+        if solutions.len() >= limit || !self.propagate(&mut grid) {
+            return;
+        }
+
+        let unknown = grid.iter().enumerate().find_map(|(r, row)| {
+            row.iter()
+                .position(|s| *s == Spring::Unknown)
+                .map(|c| (r, c))
+        });
+        match unknown {
+            None => solutions.push(grid),
+            Some((r, c)) => {
+                for guess in [Spring::Damaged, Spring::Operational] {
+                    let mut guessed = grid.clone();
+                    guessed[r][c] = guess;
+                    self.search(guessed, solutions, limit);
+                }
+            }
+        }
+    }
+
+    /// Find up to `limit` solutions to the puzzle.
+    pub fn solve(&self, limit: usize) -> Vec<Grid> {
+        let grid = vec![vec![Spring::Unknown; self.columns.len()]; self.rows.len()];
+        let mut solutions = vec![];
+        self.search(grid, &mut solutions, limit);
+        solutions
+    }
+}
+
+/// Draw a solved grid, with damaged springs filled in.
+fn render_grid(grid: &Grid) -> String {
+    grid.iter()
+        .map(|row| {
+            row.iter()
+                .map(|s| match s {
+                    Spring::Damaged => '█',
+                    Spring::Operational => '·',
+                    Spring::Unknown => '?',
+                })
+                .collect::<String>()
+                + "\n"
+        })
+        .collect()
+}
+
+/// Solve a nonogram puzzle, printing the picture and whether it is the only solution. Returns the
+/// rendered picture and whether it is unique.
+pub fn run_nonogram(input_path: PathBuf) -> Result<(String, bool), Box<dyn Error>> {
+    let puzzle_input = fs::read_to_string(input_path)?;
+    let nonogram = Nonogram::from_str(&puzzle_input)?;
+
+    let solutions = nonogram.solve(2);
+    let picture = render_grid(solutions.first().ok_or("The nonogram has no solution")?);
+    let unique = solutions.len() == 1;
+
+    print!("{picture}");
+    if unique {
+        println!("The solution is unique");
+    } else {
+        println!("The solution is not unique");
+    }
+    Ok((picture, unique))
+}
+
+fn parse_row(puzzle_input: &str, row: usize, unfold_factor: usize) -> Result<SpringRow, String> {
+    let line = puzzle_input
+        .lines()
+        .nth(row)
+        .ok_or(format!("No row {row} in the input"))?;
+    SpringRow::from_str(line)?.unfold(unfold_factor)
+}
+
+/// Print the arrangements of one row, numbered from 0, up to `limit` of them.
+pub fn run_arrangements(
+    input_path: PathBuf,
+    row: usize,
+    unfold_factor: usize,
+    limit: Option<usize>,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let puzzle_input = fs::read_to_string(input_path)?;
+    let row = parse_row(&puzzle_input, row, unfold_factor)?;
+    let arrangements = row
+        .arrangements()
+        .take(limit.unwrap_or(usize::MAX))
+        .map(|a| springs_to_string(&a))
+        .collect::<Vec<_>>();
+    for a in &arrangements {
+        println!("{a}");
+    }
+    Ok(arrangements)
+}
+
+/// Print `count` arrangements of one row, numbered from 0, picked uniformly at random.
+pub fn run_sample(
+    input_path: PathBuf,
+    row: usize,
+    unfold_factor: usize,
+    count: usize,
+    seed: Option<u64>,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let puzzle_input = fs::read_to_string(input_path)?;
+    let row = parse_row(&puzzle_input, row, unfold_factor)?;
+    let counts = row.arrangement_counts();
+    counts.total()?;
+    let mut rng = seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64);
+
+    let samples = (0..count)
+        .map(|_| counts.sample(&mut rng).map(|a| springs_to_string(&a)))
+        .collect::<Option<Vec<_>>>()
+        .ok_or("The row has no valid arrangements")?;
+    for s in &samples {
+        println!("{s}");
+    }
+    Ok(samples)
+}
+
+/// Print every row with the springs that are the same in all of its arrangements filled in.
+pub fn run_forced(
+    input_path: PathBuf,
+    unfold_factor: usize,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let puzzle_input = fs::read_to_string(input_path)?;
+    let forced = puzzle_input
+        .lines()
+        .map(|l| {
+            let row = SpringRow::from_str(l)?.unfold(unfold_factor)?;
+            row.arrangement_counts()
+                .forced_springs()
+                .map(|f| springs_to_string(&f))
+                .ok_or(format!("No valid arrangements for {l}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    for f in &forced {
+        println!("{f}");
+    }
+    Ok(forced)
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
 
+    fn solve(row: &str) -> u64 {
+        SpringRow::from_str(row)
+            .unwrap()
+            .count_arrangements()
+            .unwrap()
+    }
+
     #[test]
     fn test_case_one() {
-        let springs = vec!['?', '?', '?', '.', '#', '#', '#'];
-        let counts = vec![1, 1, 3];
-        let answer = solve(springs, 0, counts);
+        let answer = solve("???.### 1,1,3");
         assert_eq!(answer, 1)
     }
 
     #[test]
     fn test_case_two() {
-        let springs = vec![
-            '.', '?', '?', '.', '.', '?', '?', '.', '.', '.', '?', '#', '#',
-        ];
-        let counts = vec![1, 1, 3];
-        let answer = solve(springs, 0, counts);
+        let answer = solve(".??..??...?## 1,1,3");
         assert_eq!(answer, 4)
     }
 
     #[test]
     fn test_case_three() {
-        let springs = vec!['?', '#', '#', '#', '?', '?', '?', '?', '?', '?', '?', '?'];
-        let counts = vec![3, 2, 1];
-        let answer = solve(springs, 0, counts);
+        let answer = solve("?###???????? 3,2,1");
         assert_eq!(answer, 10)
     }
+
+    fn brute_force(row: &SpringRow) -> Vec<String> {
+        // Count down in binary so '#' comes first, matching lexicographic order.
+        let n = row.springs.len();
+        (0..1u32 << n)
+            .rev()
+            .map(|bits| {
+                (0..n)
+                    .map(|i| Spring::from_damaged(bits & (1 << (n - 1 - i)) != 0))
+                    .collect::<Vec<_>>()
+            })
+            .filter(|a| {
+                a.iter()
+                    .zip(&row.springs)
+                    .all(|(a, s)| s.can_be(*a == Spring::Damaged))
+            })
+            .filter(|a| {
+                let groups = springs_to_string(a)
+                    .split('.')
+                    .filter(|g| !g.is_empty())
+                    .map(|g| g.len())
+                    .collect::<Vec<_>>();
+                groups == row.groups
+            })
+            .map(|a| springs_to_string(&a))
+            .collect()
+    }
+
+    #[test]
+    fn test_arrangements() {
+        for line in [
+            "???.### 1,1,3",
+            ".??..??...?##. 1,1,3",
+            "?###???????? 3,2,1",
+            "#.# 2",
+        ] {
+            let row = SpringRow::from_str(line).unwrap();
+            let arrangements = row
+                .arrangements()
+                .map(|a| springs_to_string(&a))
+                .collect::<Vec<_>>();
+            assert_eq!(arrangements, brute_force(&row), "{line}");
+        }
+    }
+
+    #[test]
+    fn test_sample_uniform() {
+        let row = SpringRow::from_str("?###???????? 3,2,1").unwrap();
+        let counts = row.arrangement_counts();
+        let arrangements = row.arrangements().collect::<Vec<_>>();
+        let mut rng = StdRng::seed_from_u64(12);
+        let mut hits = vec![0; arrangements.len()];
+        for _ in 0..10000 {
+            let sample = counts.sample(&mut rng).unwrap();
+            hits[arrangements.iter().position(|a| *a == sample).unwrap()] += 1;
+        }
+        // Each of the 10 arrangements should come up about 1000 times.
+        assert!(hits.iter().all(|&h| (850..1150).contains(&h)), "{hits:?}");
+    }
+
+    #[test]
+    fn test_forced_springs() {
+        let forced = |line: &str| {
+            SpringRow::from_str(line)
+                .unwrap()
+                .arrangement_counts()
+                .forced_springs()
+                .map(|f| springs_to_string(&f))
+        };
+        assert_eq!(forced("???.### 1,1,3"), Some("#.#.###".to_string()));
+        assert_eq!(
+            forced("?###???????? 3,2,1"),
+            Some(".###.???????".to_string())
+        );
+        assert_eq!(forced("?????? 4"), Some("??##??".to_string()));
+        assert_eq!(forced("#.# 2"), None);
+    }
+
+    #[test]
+    fn test_unfold() {
+        let row = SpringRow::from_str("?###???????? 3,2,1").unwrap();
+        assert_eq!(row.unfold(5).unwrap().count_arrangements(), Ok(506250));
+        assert_eq!(row.unfold(1).unwrap(), row);
+        assert!(row.unfold(0).is_err());
+    }
+
+    #[test]
+    fn test_too_many_arrangements() {
+        let row = SpringRow::from_str("??? 1").unwrap();
+        assert!(row.unfold(10).unwrap().count_arrangements().is_ok());
+        let unfolded = row.unfold(60).unwrap();
+        let counts = unfolded.arrangement_counts();
+        assert!(counts.total().is_err());
+        assert!(counts.forced_springs().is_some());
+        assert!(unfolded.arrangements().next().is_some());
+    }
+
+    #[test]
+    fn test_nonogram_blank_lines() {
+        let nonogram = Nonogram::from_str("\nrows\n1\n\n\ncolumns\n1\n\n").unwrap();
+        assert_eq!(nonogram.rows, vec![vec![1]]);
+        assert_eq!(nonogram.columns, vec![vec![1]]);
+        assert_eq!(nonogram.solve(2).len(), 1);
+    }
 }
@@ -1,13 +1,70 @@
 use clap::Parser;
 use std::process;
 
-use day12::run;
+use day12::{
+    default_unfold_factor, run_arrangements, run_forced, run_nonogram, run_sample, run_with_unfold,
+};
 use util::Cli;
 
+#[derive(Parser)]
+struct Day12Cli {
+    #[command(flatten)]
+    cli: Cli,
+
+    /// Repeat each row this many times, instead of the factor for the part.
+    #[arg(long)]
+    unfold: Option<usize>,
+
+    /// List the arrangements of this row instead, numbered from 0, in lexicographic order.
+    #[arg(long, value_name = "ROW")]
+    list: Option<usize>,
+
+    /// Stop listing arrangements after this many.
+    #[arg(long, requires = "list")]
+    limit: Option<usize>,
+
+    /// Print arrangements of this row picked uniformly at random instead, numbered from 0.
+    #[arg(long, value_name = "ROW")]
+    sample: Option<usize>,
+
+    /// How many arrangements to sample.
+    #[arg(long, default_value_t = 1, requires = "sample")]
+    samples: usize,
+
+    /// Seed for sampling, so the same arrangements are picked each time.
+    #[arg(long, requires = "sample")]
+    seed: Option<u64>,
+
+    /// Print every row with the springs that are forced in all arrangements filled in instead.
+    #[arg(long)]
+    forced: bool,
+
+    /// Treat the input as a nonogram puzzle, with a section of row clues then column clues, and
+    /// solve it instead.
+    #[arg(long)]
+    nonogram: bool,
+}
+
 fn main() {
-    let cli = Cli::parse();
+    let cli = Day12Cli::parse();
+    let path = cli.cli.puzzle_input_path;
+    let unfold_factor = cli
+        .unfold
+        .unwrap_or(default_unfold_factor(cli.cli.part_two));
+
+    let result = if cli.nonogram {
+        run_nonogram(path).map(|_| ())
+    } else if let Some(row) = cli.list {
+        run_arrangements(path, row, unfold_factor, cli.limit).map(|_| ())
+    } else if let Some(row) = cli.sample {
+        run_sample(path, row, unfold_factor, cli.samples, cli.seed).map(|_| ())
+    } else if cli.forced {
+        run_forced(path, unfold_factor).map(|_| ())
+    } else {
+        run_with_unfold(path, unfold_factor).map(|_| ())
+    };
 
-    if let Err(e) = run(cli.puzzle_input_path, cli.part_two) {
+    if let Err(e) = result {
         eprintln!("Error: {e}");
         process::exit(1);
     }
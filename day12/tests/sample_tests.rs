@@ -15,3 +15,89 @@ fn test_part_two() {
     let answer = day12::run(input_path, true).unwrap();
     assert_eq!(answer, 525152);
 }
+
+#[test]
+fn test_unfold_factor() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let answer = day12::run_with_unfold(input_path.clone(), 2).unwrap();
+    assert_eq!(answer, 206);
+    assert!(day12::run_with_unfold(input_path.clone(), 0).is_err());
+    assert!(day12::run_forced(input_path.clone(), 0).is_err());
+    assert!(day12::run_arrangements(input_path.clone(), 0, 0, None).is_err());
+    assert!(day12::run_sample(input_path, 0, 0, 1, Some(1)).is_err());
+}
+
+#[test]
+fn test_list_arrangements() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let arrangements = day12::run_arrangements(input_path.clone(), 1, 1, None).unwrap();
+    assert_eq!(
+        arrangements,
+        vec![
+            ".#...#....###.",
+            ".#....#...###.",
+            "..#..#....###.",
+            "..#...#...###."
+        ]
+    );
+    let arrangements = day12::run_arrangements(input_path, 5, 5, Some(3)).unwrap();
+    assert_eq!(arrangements.len(), 3);
+}
+
+#[test]
+fn test_sample_arrangements() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let samples = day12::run_sample(input_path.clone(), 5, 1, 20, Some(7)).unwrap();
+    let arrangements = day12::run_arrangements(input_path.clone(), 5, 1, None).unwrap();
+    assert!(samples.iter().all(|s| arrangements.contains(s)));
+    assert_eq!(
+        samples,
+        day12::run_sample(input_path, 5, 1, 20, Some(7)).unwrap()
+    );
+}
+
+#[test]
+fn test_forced() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let forced = day12::run_forced(input_path, 1).unwrap();
+    assert_eq!(
+        forced,
+        vec![
+            "#.#.###",
+            ".??..??...###.",
+            ".#.###.#.######",
+            "####.#...#...",
+            "????.######..#####.",
+            ".###.???????"
+        ]
+    );
+}
+
+#[test]
+fn test_nonogram() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/nonogram.txt");
+    let (picture, unique) = day12::run_nonogram(input_path).unwrap();
+    assert_eq!(picture, "·█·█·\n█████\n█████\n·███·\n··█··\n");
+    assert!(unique);
+}
+
+#[test]
+fn test_nonogram_ambiguous() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/nonogram_ambiguous.txt");
+    let (picture, unique) = day12::run_nonogram(input_path).unwrap();
+    assert_eq!(picture, "█·\n·█\n");
+    assert!(!unique);
+}
+
+#[test]
+fn test_nonogram_unsolvable() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/nonogram_unsolvable.txt");
+    assert!(day12::run_nonogram(input_path).is_err());
+}
@@ -1,58 +1,627 @@
-use std::{error::Error, fs, iter::zip, path::PathBuf};
+use std::{cmp::Ordering, error::Error, fmt, fs, ops::Range, path::PathBuf, str::FromStr};
 
-use util::transpose;
+/// Which way a mirror line runs through a pattern.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MirrorLine {
+    /// Between two rows.
+    Horizontal,
+    /// Between two columns.
+    Vertical,
+}
+
+/// A mirror line which reflects the pattern apart from a few smudges.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reflection {
+    pub line: MirrorLine,
+    /// Number of rows above, or columns left of, the line.
+    pub split: usize,
+    /// The `(row, column)` of each smudge, taking the cell on the near side of the line.
+    pub differences: Vec<(usize, usize)>,
+}
+
+impl Reflection {
+    /// The puzzle's summary of the line.
+    pub fn score(&self) -> usize {
+        match self.line {
+            MirrorLine::Horizontal => 100 * self.split,
+            MirrorLine::Vertical => self.split,
+        }
+    }
+}
+
+impl fmt::Display for Reflection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (line, side) = match self.line {
+            MirrorLine::Horizontal => ("Horizontal", "rows"),
+            MirrorLine::Vertical => ("Vertical", "columns"),
+        };
+        write!(f, "{line} line after {} {side}", self.split)?;
+        if !self.differences.is_empty() {
+            let differences = self
+                .differences
+                .iter()
+                .map(|(r, c)| format!("({r}, {c})"))
+                .collect::<Vec<_>>();
+            write!(f, ", smudged at {}", differences.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// A symmetry of part of a pattern other than the straight mirror lines. Centres are given in
+/// half-cell steps, so a centre of `(3, 4)` is at row 1.5, column 2.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Symmetry {
+    /// Reflection in the line `row - column = offset`, running down to the right across the
+    /// largest square sub-block it passes through corner to corner.
+    Diagonal { offset: isize },
+    /// Reflection in the line `row + column = sum`, running down to the left.
+    AntiDiagonal { sum: usize },
+    /// Rotation by 180 degrees of the largest sub-block centred on `centre`.
+    HalfTurn { centre: (usize, usize) },
+    /// Rotation by 90 degrees of the largest square sub-block centred on `centre`.
+    QuarterTurn { centre: (usize, usize) },
+}
+
+/// Format a position given in half-cell steps.
+fn half_cells(x: usize) -> String {
+    if x.is_multiple_of(2) {
+        format!("{}", x / 2)
+    } else {
+        format!("{}.5", x / 2)
+    }
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Symmetry::Diagonal { offset } => write!(f, "Diagonal line row - column = {offset}"),
+            Symmetry::AntiDiagonal { sum } => write!(f, "Anti-diagonal line row + column = {sum}"),
+            Symmetry::HalfTurn { centre } => write!(
+                f,
+                "Half turn about ({}, {})",
+                half_cells(centre.0),
+                half_cells(centre.1)
+            ),
+            Symmetry::QuarterTurn { centre } => write!(
+                f,
+                "Quarter turn about ({}, {})",
+                half_cells(centre.0),
+                half_cells(centre.1)
+            ),
+        }
+    }
+}
+
+/// A symmetry which holds apart from a few smudges.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymmetryMatch {
+    pub symmetry: Symmetry,
+    /// The `(row, column)` of each smudge.
+    pub differences: Vec<(usize, usize)>,
+}
 
-fn find_vertical_reflection(block: &Vec<Vec<char>>, part_two: bool) -> i64 {
-    for split in 1..block.len() {
-        let mut upper = block[..split].to_vec();
-        let mut lower = block[split..].to_vec();
-        if lower.len() > upper.len() {
-            lower = lower[..upper.len()].to_vec();
-        } else if lower.len() < upper.len() {
-            upper = upper[(upper.len() - lower.len())..].to_vec()
+impl fmt::Display for SymmetryMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symmetry)?;
+        if !self.differences.is_empty() {
+            let differences = self
+                .differences
+                .iter()
+                .map(|(r, c)| format!("({r}, {c})"))
+                .collect::<Vec<_>>();
+            write!(f, ", smudged at {}", differences.join(", "))?;
         }
+        Ok(())
+    }
+}
 
-        lower.reverse();
+/// The symmetry group of a whole pattern along with the symmetries of its sub-blocks.
+pub type PatternSymmetry = (SymmetryGroup, Vec<SymmetryMatch>);
 
-        if part_two {
-            let diffs = zip(upper, lower)
-                .flat_map(|(a, b)| zip(a, b).filter(|(x, y)| *x != *y))
-                .count();
-            if diffs == 1 {
-                return split as i64;
+/// Which symmetries of the square the whole of a pattern has, each about its centre.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SymmetryGroup {
+    pub quarter_turn: bool,
+    pub half_turn: bool,
+    pub horizontal: bool,
+    pub vertical: bool,
+    pub diagonal: bool,
+    pub anti_diagonal: bool,
+}
+
+impl SymmetryGroup {
+    /// Name of the group in Schoenflies-style notation. With smudges allowed the symmetries
+    /// found might not form a group, in which case it is irregular.
+    pub fn name(&self) -> &'static str {
+        let straight = self.horizontal || self.vertical;
+        let diagonal = self.diagonal || self.anti_diagonal;
+        match *self {
+            Self {
+                quarter_turn: true,
+                half_turn: true,
+                horizontal: true,
+                vertical: true,
+                diagonal: true,
+                anti_diagonal: true,
+            } => "D4",
+            Self {
+                quarter_turn: true,
+                half_turn: true,
+                ..
+            } if !straight && !diagonal => "C4",
+            Self {
+                quarter_turn: false,
+                half_turn: true,
+                horizontal: true,
+                vertical: true,
+                ..
+            } if !diagonal => "D2",
+            Self {
+                quarter_turn: false,
+                half_turn: true,
+                diagonal: true,
+                anti_diagonal: true,
+                ..
+            } if !straight => "D2",
+            Self {
+                quarter_turn: false,
+                half_turn: true,
+                ..
+            } if !straight && !diagonal => "C2",
+            Self {
+                quarter_turn: false,
+                half_turn: false,
+                ..
+            } if [
+                self.horizontal,
+                self.vertical,
+                self.diagonal,
+                self.anti_diagonal,
+            ]
+            .iter()
+            .filter(|s| **s)
+            .count()
+                <= 1 =>
+            {
+                if straight || diagonal {
+                    "D1"
+                } else {
+                    "C1"
+                }
             }
+            _ => "irregular",
+        }
+    }
+}
+
+impl fmt::Display for SymmetryGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let elements = [
+            (self.quarter_turn, "quarter turn"),
+            (self.half_turn, "half turn"),
+            (self.horizontal, "horizontal mirror"),
+            (self.vertical, "vertical mirror"),
+            (self.diagonal, "diagonal mirror"),
+            (self.anti_diagonal, "anti-diagonal mirror"),
+        ]
+        .iter()
+        .filter(|(present, _)| *present)
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>();
+
+        if elements.is_empty() {
+            write!(f, "{}", self.name())
         } else {
-            if upper == lower {
-                return split as i64;
+            write!(f, "{} ({})", self.name(), elements.join(", "))
+        }
+    }
+}
+
+/// One block of the notes, with each row and column stored as a bitmask of where the rocks are.
+/// Since the masks are `u64`s, patterns can be at most 64 rows and 64 columns, and parsing a
+/// larger one fails.
+pub struct Pattern {
+    rows: Vec<u64>,
+    columns: Vec<u64>,
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let block = s
+            .lines()
+            .map(|l| l.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let width = block.first().map_or(0, |row| row.len());
+        if block.iter().any(|row| row.len() != width) {
+            return Err("Pattern rows are not all the same length".to_string());
+        }
+        if width > 64 || block.len() > 64 {
+            return Err("Patterns can be at most 64 rows and columns".to_string());
+        }
+
+        let mut rows = vec![0; block.len()];
+        let mut columns = vec![0; width];
+        for (r, row) in block.iter().enumerate() {
+            for (c, ch) in row.iter().enumerate() {
+                match ch {
+                    '#' => {
+                        rows[r] |= 1 << c;
+                        columns[c] |= 1 << r;
+                    }
+                    '.' => (),
+                    _ => return Err(format!("Invalid character in pattern: {ch}")),
+                }
             }
         }
+        Ok(Self { rows, columns })
     }
-    0
 }
 
-fn parse_block(block_str: &str, part_two: bool) -> i64 {
-    let block = block_str
-        .lines()
-        .map(|l| l.chars().collect::<Vec<_>>())
-        .collect::<Vec<_>>();
+/// Every split of `lines` that mirrors them with exactly `smudges` differing bits, along with the
+/// line and bit of each difference on the near side of the split.
+fn find_reflections(lines: &[u64], smudges: usize) -> Vec<(usize, Vec<(usize, usize)>)> {
+    (1..lines.len())
+        .filter_map(|split| {
+            // Pair each line before the split with its mirror image after it, stopping at
+            // whichever edge is nearer.
+            let mut differences = vec![];
+            for (near, far) in (0..split).rev().zip(split..lines.len()) {
+                let mut diff = lines[near] ^ lines[far];
+                if differences.len() + diff.count_ones() as usize > smudges {
+                    return None;
+                }
+                while diff != 0 {
+                    differences.push((near, diff.trailing_zeros() as usize));
+                    diff &= diff - 1;
+                }
+            }
+            (differences.len() == smudges).then_some((split, differences))
+        })
+        .collect()
+}
 
-    let v_reflect = find_vertical_reflection(&block, part_two);
-    let block_transpose = transpose(&block);
-    let h_reflect = find_vertical_reflection(&block_transpose, part_two);
+impl Pattern {
+    /// Every mirror line which reflects the pattern with exactly `smudges` smudges, horizontal
+    /// lines first.
+    pub fn reflections(&self, smudges: usize) -> Vec<Reflection> {
+        let horizontal =
+            find_reflections(&self.rows, smudges)
+                .into_iter()
+                .map(|(split, differences)| Reflection {
+                    line: MirrorLine::Horizontal,
+                    split,
+                    differences,
+                });
+        let vertical =
+            find_reflections(&self.columns, smudges)
+                .into_iter()
+                .map(|(split, differences)| Reflection {
+                    line: MirrorLine::Vertical,
+                    split,
+                    // The columns' bits are rows, so swap back to (row, column).
+                    differences: differences.into_iter().map(|(c, r)| (r, c)).collect(),
+                });
+        horizontal.chain(vertical).collect()
+    }
 
-    h_reflect + (100 * v_reflect)
+    /// The first mirror line of each orientation with exactly `smudges` smudges.
+    pub fn first_reflections(&self, smudges: usize) -> (Option<Reflection>, Option<Reflection>) {
+        let reflections = self.reflections(smudges);
+        let first = |line| reflections.iter().find(|r| r.line == line).cloned();
+        (first(MirrorLine::Horizontal), first(MirrorLine::Vertical))
+    }
 }
 
-fn compute_answer(puzzle_input: &str, part_two: bool) -> i64 {
+impl Pattern {
+    fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn width(&self) -> usize {
+        self.columns.len()
+    }
+
+    fn is_rock(&self, (r, c): (usize, usize)) -> bool {
+        self.rows[r] >> c & 1 == 1
+    }
+
+    /// The cells which would need to change for `transform` to map the rectangle of `rows` and
+    /// `columns` onto itself, or `None` if there are more than `limit` of them. The transform
+    /// splits the cells into orbits which must all match, and in each orbit the odd ones out
+    /// are counted. If an orbit is split evenly, the cells that differ from its last cell are
+    /// taken, which for a mirror is the cell on the near side.
+    fn differences(
+        &self,
+        rows: Range<usize>,
+        columns: Range<usize>,
+        transform: impl Fn((usize, usize)) -> (usize, usize),
+        limit: usize,
+    ) -> Option<Vec<(usize, usize)>> {
+        let mut visited = vec![false; self.height() * self.width()];
+        let mut differences = vec![];
+
+        for r in rows {
+            for c in columns.clone() {
+                if visited[r * self.width() + c] {
+                    continue;
+                }
+
+                let mut orbit = vec![(r, c)];
+                let mut cell = transform((r, c));
+                while cell != (r, c) {
+                    orbit.push(cell);
+                    cell = transform(cell);
+                }
+                for &(r, c) in &orbit {
+                    visited[r * self.width() + c] = true;
+                }
+
+                let rocks = orbit.iter().filter(|&&cell| self.is_rock(cell)).count();
+                let odd_one_out = match (2 * rocks).cmp(&orbit.len()) {
+                    Ordering::Less => true,
+                    Ordering::Greater => false,
+                    Ordering::Equal => !self.is_rock(*orbit.last().unwrap()),
+                };
+                differences.extend(
+                    orbit
+                        .into_iter()
+                        .filter(|&cell| self.is_rock(cell) == odd_one_out),
+                );
+                if differences.len() > limit {
+                    return None;
+                }
+            }
+        }
+        Some(differences)
+    }
+
+    /// Range of cells along an axis of length `len` that is centred on `centre`, given in
+    /// half-cell steps, and as large as possible.
+    fn centred_range(centre: usize, len: usize) -> Range<usize> {
+        let start = centre.saturating_sub(len - 1);
+        start..(centre - start + 1)
+    }
+
+    /// Every diagonal mirror line and rotation centre for a sub-block at least 2 by 2 cells, with
+    /// exactly `smudges` smudges.
+    pub fn symmetries(&self, smudges: usize) -> Vec<SymmetryMatch> {
+        let (height, width) = (self.height() as isize, self.width() as isize);
+        let mut symmetries = vec![];
+        let mut check =
+            |symmetry, rows: Range<usize>, columns: Range<usize>, transform: &dyn Fn(_) -> _| {
+                // A single cell, or a single row or column of cells, trivially matches itself.
+                if rows.len() < 2 || columns.len() < 2 {
+                    return;
+                }
+                if let Some(differences) = self.differences(rows, columns, transform, smudges) {
+                    if differences.len() == smudges {
+                        symmetries.push(SymmetryMatch {
+                            symmetry,
+                            differences,
+                        });
+                    }
+                }
+            };
+
+        for offset in (2 - width)..=(height - 2) {
+            // The line runs through the square sub-block from its top left corner at
+            // (offset, 0) or (0, -offset).
+            let (top, left) = (offset.max(0), (-offset).max(0));
+            let side = (height - top).min(width - left);
+            check(
+                Symmetry::Diagonal { offset },
+                top as usize..(top + side) as usize,
+                left as usize..(left + side) as usize,
+                &|(r, c)| {
+                    (
+                        (c as isize + offset) as usize,
+                        (r as isize - offset) as usize,
+                    )
+                },
+            );
+        }
+
+        for sum in 1..(self.height() + self.width()).saturating_sub(2) {
+            let rows = sum.saturating_sub(self.width() - 1)..(sum + 1).min(self.height());
+            let columns = sum.saturating_sub(self.height() - 1)..(sum + 1).min(self.width());
+            check(Symmetry::AntiDiagonal { sum }, rows, columns, &|(r, c)| {
+                (sum - c, sum - r)
+            });
+        }
+
+        for row_centre in 1..(2 * self.height()).saturating_sub(2) {
+            for column_centre in 1..(2 * self.width()).saturating_sub(2) {
+                let rows = Self::centred_range(row_centre, self.height());
+                let columns = Self::centred_range(column_centre, self.width());
+                let centre = (row_centre, column_centre);
+                check(
+                    Symmetry::HalfTurn { centre },
+                    rows.clone(),
+                    columns.clone(),
+                    &|(r, c)| (row_centre - r, column_centre - c),
+                );
+
+                // Quarter turns need a square sub-block, with the centre in the middle of a cell
+                // or on the corner between cells.
+                if row_centre % 2 == column_centre % 2 {
+                    let side = rows.len().min(columns.len());
+                    let top = (row_centre + 1 - side) / 2;
+                    let left = (column_centre + 1 - side) / 2;
+                    check(
+                        Symmetry::QuarterTurn { centre },
+                        top..(top + side),
+                        left..(left + side),
+                        &|(r, c)| {
+                            (
+                                (row_centre + 2 * c - column_centre) / 2,
+                                (row_centre + column_centre - 2 * r) / 2,
+                            )
+                        },
+                    );
+                }
+            }
+        }
+        symmetries
+    }
+
+    /// Which symmetries the whole pattern has about its centre, with exactly `smudges` smudges as
+    /// for the other mirror lines. Quarter turns and diagonal mirrors only apply to square
+    /// patterns.
+    pub fn symmetry_group(&self, smudges: usize) -> SymmetryGroup {
+        let (height, width) = (self.height(), self.width());
+        let holds = |transform: &dyn Fn((usize, usize)) -> (usize, usize)| {
+            self.differences(0..height, 0..width, transform, smudges)
+                .is_some_and(|differences| differences.len() == smudges)
+        };
+        let square = height == width;
+
+        SymmetryGroup {
+            quarter_turn: square && holds(&|(r, c)| (c, width - 1 - r)),
+            half_turn: holds(&|(r, c)| (height - 1 - r, width - 1 - c)),
+            horizontal: holds(&|(r, c)| (height - 1 - r, c)),
+            vertical: holds(&|(r, c)| (r, width - 1 - c)),
+            diagonal: square && holds(&|(r, c)| (c, r)),
+            anti_diagonal: square && holds(&|(r, c)| (width - 1 - c, height - 1 - r)),
+        }
+    }
+}
+
+/// Split the notes into patterns, ignoring any extra blank lines between or after them.
+fn parse_blocks(puzzle_input: &str) -> Result<Vec<Pattern>, String> {
     puzzle_input
         .split("\n\n")
-        .map(|b| parse_block(&b, part_two))
-        .sum()
+        .map(str::trim)
+        .filter(|b| !b.is_empty())
+        .map(Pattern::from_str)
+        .collect()
+}
+
+/// A pattern with no reflection scores nothing.
+fn compute_answer(puzzle_input: &str, smudges: usize) -> Result<usize, String> {
+    Ok(parse_blocks(puzzle_input)?
+        .iter()
+        .map(|pattern| {
+            let (horizontal, vertical) = pattern.first_reflections(smudges);
+            horizontal
+                .iter()
+                .chain(&vertical)
+                .map(|r| r.score())
+                .sum::<usize>()
+        })
+        .sum())
 }
 
-pub fn run(input_path: PathBuf, part_two: bool) -> Result<i64, Box<dyn Error>> {
+/// The number of smudges given by the puzzle for each part.
+pub fn default_smudges(part_two: bool) -> usize {
+    if part_two {
+        1
+    } else {
+        0
+    }
+}
+
+pub fn run(input_path: PathBuf, part_two: bool) -> Result<usize, Box<dyn Error>> {
+    run_with_smudges(input_path, default_smudges(part_two))
+}
+
+/// Solve with exactly `smudges` smudges on each mirror line.
+pub fn run_with_smudges(input_path: PathBuf, smudges: usize) -> Result<usize, Box<dyn Error>> {
     let puzzle_input = fs::read_to_string(input_path)?;
-    let answer = compute_answer(&puzzle_input, part_two);
+    let answer = compute_answer(&puzzle_input, smudges)?;
     println!("The answer is {answer}");
     Ok(answer)
 }
+
+/// Print every mirror line with exactly `smudges` smudges in each pattern.
+pub fn run_axes(
+    input_path: PathBuf,
+    smudges: usize,
+) -> Result<Vec<Vec<Reflection>>, Box<dyn Error>> {
+    let puzzle_input = fs::read_to_string(input_path)?;
+    let reflections = parse_blocks(&puzzle_input)?
+        .iter()
+        .map(|pattern| pattern.reflections(smudges))
+        .collect::<Vec<_>>();
+
+    for (i, pattern_reflections) in reflections.iter().enumerate() {
+        println!("Pattern {i}:");
+        if pattern_reflections.is_empty() {
+            println!("  No reflections");
+        }
+        for reflection in pattern_reflections {
+            println!("  {reflection}");
+        }
+    }
+    Ok(reflections)
+}
+
+/// Print the symmetry group of each pattern, followed by every diagonal mirror line and rotation
+/// centre with exactly `smudges` smudges.
+pub fn run_symmetry(
+    input_path: PathBuf,
+    smudges: usize,
+) -> Result<Vec<PatternSymmetry>, Box<dyn Error>> {
+    let puzzle_input = fs::read_to_string(input_path)?;
+    let symmetries = parse_blocks(&puzzle_input)?
+        .iter()
+        .map(|pattern| (pattern.symmetry_group(smudges), pattern.symmetries(smudges)))
+        .collect::<Vec<_>>();
+
+    for (i, (group, pattern_symmetries)) in symmetries.iter().enumerate() {
+        println!("Pattern {i}: {group}");
+        for symmetry in pattern_symmetries {
+            println!("  {symmetry}");
+        }
+    }
+    Ok(symmetries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_reflection() {
+        let pattern = Pattern::from_str("#.\n..").unwrap();
+        assert_eq!(pattern.first_reflections(0), (None, None));
+        assert_eq!(compute_answer("#.\n..", 0), Ok(0));
+    }
+
+    #[test]
+    fn test_trailing_blank_lines() {
+        assert_eq!(compute_answer("##\n..\n\n\n..\n..\n\n", 0), Ok(1 + 100 + 1));
+        assert_eq!(parse_blocks("#.\n..\n\n\n\n").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_single_line_symmetries() {
+        for pattern in ["#..#.", "#\n.\n.\n#\n."] {
+            let pattern = Pattern::from_str(pattern).unwrap();
+            assert!(pattern.symmetries(0).is_empty());
+            assert!(pattern.symmetries(1).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_every_axis() {
+        let pattern = Pattern::from_str("####\n####").unwrap();
+        let splits = pattern
+            .reflections(0)
+            .iter()
+            .map(|r| (r.line, r.split))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            splits,
+            vec![
+                (MirrorLine::Horizontal, 1),
+                (MirrorLine::Vertical, 1),
+                (MirrorLine::Vertical, 2),
+                (MirrorLine::Vertical, 3)
+            ]
+        );
+    }
+}
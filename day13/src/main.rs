@@ -1,13 +1,42 @@
 use clap::Parser;
 use std::process;
 
-use day13::run;
+use day13::{default_smudges, run_axes, run_symmetry, run_with_smudges};
 use util::Cli;
 
+#[derive(Parser)]
+struct Day13Cli {
+    #[command(flatten)]
+    cli: Cli,
+
+    /// Number of smudges each mirror line must have, instead of the number for the part.
+    #[arg(long)]
+    smudges: Option<usize>,
+
+    /// Print every mirror line in each pattern, along with where the smudges are.
+    #[arg(long)]
+    axes: bool,
+
+    /// Print the symmetry group of each pattern, along with every diagonal mirror line and
+    /// rotation centre.
+    #[arg(long)]
+    symmetry: bool,
+}
+
 fn main() {
-    let cli = Cli::parse();
+    let cli = Day13Cli::parse();
+    let path = cli.cli.puzzle_input_path;
+    let smudges = cli.smudges.unwrap_or(default_smudges(cli.cli.part_two));
+
+    let result = if cli.symmetry {
+        run_symmetry(path, smudges).map(|_| ())
+    } else if cli.axes {
+        run_axes(path, smudges).map(|_| ())
+    } else {
+        run_with_smudges(path, smudges).map(|_| ())
+    };
 
-    if let Err(e) = run(cli.puzzle_input_path, cli.part_two) {
+    if let Err(e) = result {
         eprintln!("Error: {e}");
         process::exit(1);
     }
@@ -1,5 +1,7 @@
 use std::path::PathBuf;
 
+use day13::Symmetry;
+
 #[test]
 fn test_part_one() {
     let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -15,3 +17,78 @@ fn test_part_two() {
     let answer = day13::run(input_path, true).unwrap();
     assert_eq!(answer, 400);
 }
+
+#[test]
+fn test_axes_with_smudges() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let reflections = day13::run_axes(input_path, 1).unwrap();
+    let descriptions = reflections
+        .iter()
+        .map(|r| r.iter().map(|r| r.to_string()).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        descriptions,
+        vec![
+            vec!["Horizontal line after 3 rows, smudged at (0, 0)"],
+            vec!["Horizontal line after 1 rows, smudged at (0, 4)"]
+        ]
+    );
+}
+
+#[test]
+fn test_symmetry_groups() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/symmetry_input.txt");
+    let symmetries = day13::run_symmetry(input_path, 0).unwrap();
+    let names = symmetries
+        .iter()
+        .map(|(group, _)| group.name())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["D4", "D1", "D1", "D2", "D4"]);
+    assert!(symmetries[1].0.diagonal);
+    assert!(symmetries[2].0.horizontal);
+}
+
+#[test]
+fn test_smudged_symmetry() {
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/smudged_symmetry.txt");
+    let (group, _) = day13::run_symmetry(input_path.clone(), 0)
+        .unwrap()
+        .remove(0);
+    assert_eq!(group.to_string(), "D1 (diagonal mirror)");
+
+    // With exactly one smudge the diagonal mirror, which has none, no longer counts.
+    let (group, symmetries) = day13::run_symmetry(input_path, 1).unwrap().remove(0);
+    assert_eq!(
+        group.to_string(),
+        "irregular (quarter turn, half turn, horizontal mirror, vertical mirror, \
+            anti-diagonal mirror)"
+    );
+    let quarter_turn = symmetries
+        .iter()
+        .find(|s| s.symmetry == Symmetry::QuarterTurn { centre: (3, 3) })
+        .unwrap();
+    assert_eq!(quarter_turn.differences, vec![(3, 3)]);
+    assert_eq!(
+        quarter_turn.to_string(),
+        "Quarter turn about (1.5, 1.5), smudged at (3, 3)"
+    );
+}
+
+#[test]
+fn test_perfect_symmetry_with_smudges() {
+    // The first pattern is perfectly symmetric, so none of its symmetries have exactly one
+    // smudge, matching the mirror lines and rotation centres found with one smudge.
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/symmetry_input.txt");
+    let (group, symmetries) = day13::run_symmetry(input_path.clone(), 1)
+        .unwrap()
+        .remove(0);
+    assert_eq!(group.name(), "C1");
+    assert!(!symmetries
+        .iter()
+        .any(|s| s.symmetry == Symmetry::QuarterTurn { centre: (3, 3) }));
+    assert!(day13::run_axes(input_path, 1).unwrap()[0].is_empty());
+}
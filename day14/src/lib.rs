@@ -1,123 +1,598 @@
-use std::{collections::HashMap, error::Error, fs, path::PathBuf};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    error::Error,
+    fmt, fs,
+    hash::{Hash, Hasher},
+    iter::Peekable,
+    ops::Range,
+    path::PathBuf,
+    str::{Chars, FromStr},
+};
 
-use util::transpose;
+const SPINS: usize = 1000000000;
 
-enum TiltDirection {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TiltDirection {
     North,
     East,
     South,
     West,
 }
 
-fn flip(rocks: &Vec<Vec<char>>) -> Vec<Vec<char>> {
-    rocks
-        .iter()
-        .map(|l| {
-            let mut l_reversed = l.clone();
-            l_reversed.reverse();
-            l_reversed
-        })
-        .collect()
-}
-
-fn tilt_rocks(rocks: &Vec<Vec<char>>, direction: &TiltDirection) -> Vec<Vec<char>> {
-    let mut rocks_tilted = match direction {
-        TiltDirection::North => flip(&transpose(&rocks)),
-        TiltDirection::East => rocks.clone(),
-        TiltDirection::South => transpose(&rocks),
-        TiltDirection::West => flip(&rocks.clone()),
-    };
-
-    rocks_tilted = rocks_tilted
-        .iter()
-        .map(|column| {
-            column
-                .split(|c| c == &'#')
-                .map(|col| {
-                    let mut col_sorted = col.to_vec();
-                    col_sorted.sort();
-                    col_sorted
-                })
-                .collect::<Vec<_>>()
-                .join(&'#')
-        })
-        .collect::<Vec<_>>();
+impl FromStr for TiltDirection {
+    type Err = String;
 
-    match direction {
-        TiltDirection::North => transpose(&flip(&rocks_tilted)),
-        TiltDirection::East => rocks_tilted,
-        TiltDirection::South => transpose(&rocks_tilted),
-        TiltDirection::West => flip(&rocks_tilted),
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "n" | "north" => Ok(Self::North),
+            "e" | "east" => Ok(Self::East),
+            "s" | "south" => Ok(Self::South),
+            "w" | "west" => Ok(Self::West),
+            _ => Err(format!("Unknown direction {s}, expected N, E, S or W")),
+        }
     }
 }
 
-fn spin_rocks(rocks: Vec<Vec<char>>) -> Vec<Vec<char>> {
-    let spin_cycle = [
-        TiltDirection::North,
-        TiltDirection::West,
-        TiltDirection::South,
-        TiltDirection::East,
-    ];
+const SPIN_CYCLE: [TiltDirection; 4] = [
+    TiltDirection::North,
+    TiltDirection::West,
+    TiltDirection::South,
+    TiltDirection::East,
+];
 
-    spin_cycle.iter().fold(rocks, |r, c| tilt_rocks(&r, c))
+/// Transpose a 64 by 64 block of bits in place, so bit `j` of `a[k]` moves to bit `k` of `a[j]`.
+/// Swaps the off-diagonal halves, then quarters and so on down to single bits.
+fn transpose_block(a: &mut [u64; 64]) {
+    let mut j = 32;
+    let mut m = 0x0000_0000_FFFF_FFFFu64;
+    while j != 0 {
+        let mut k = 0;
+        while k < 64 {
+            let t = ((a[k] >> j) ^ a[k + j]) & m;
+            a[k] ^= t << j;
+            a[k + j] ^= t;
+            k = (k + j + 1) & !j;
+        }
+        j >>= 1;
+        m ^= m << j;
+    }
 }
 
-fn compute_answer(puzzle_input: &str, part_two: bool) -> i64 {
-    let rocks = puzzle_input
-        .lines()
-        .map(|l| l.chars().collect::<Vec<_>>())
-        .collect::<Vec<_>>();
+/// Mask of the bits in word `w` of a line that fall in `range`.
+fn word_mask(w: usize, range: &Range<usize>) -> u64 {
+    let low = range.start.clamp(w * 64, (w + 1) * 64) - w * 64;
+    let high = range.end.clamp(w * 64, (w + 1) * 64) - w * 64;
+    if high <= low {
+        0
+    } else {
+        (u64::MAX >> (64 - high + low)) << low
+    }
+}
 
-    let n_rows = rocks.len();
+/// A grid of bits stored as a number of lines, each packed into 64-bit words.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Bitboard {
+    n_lines: usize,
+    len: usize,
+    words_per_line: usize,
+    words: Vec<u64>,
+}
 
-    let mut rocks_tilted: Vec<Vec<char>>;
+impl Bitboard {
+    fn new(n_lines: usize, len: usize) -> Self {
+        let words_per_line = len.div_ceil(64);
+        Self {
+            n_lines,
+            len,
+            words_per_line,
+            words: vec![0; n_lines * words_per_line],
+        }
+    }
 
-    if part_two {
-        let mut state_history = HashMap::new();
-        state_history.insert(rocks.clone(), 0);
+    fn line(&self, line: usize) -> &[u64] {
+        &self.words[line * self.words_per_line..(line + 1) * self.words_per_line]
+    }
 
-        let mut cycle_detected = false;
-        let mut i = 0;
-        let mut j = 0;
-        rocks_tilted = rocks;
+    fn get(&self, line: usize, i: usize) -> bool {
+        self.line(line)[i / 64] >> (i % 64) & 1 == 1
+    }
 
-        while !cycle_detected {
-            rocks_tilted = spin_rocks(rocks_tilted);
-            i += 1;
+    fn set(&mut self, line: usize, i: usize) {
+        self.words[line * self.words_per_line + i / 64] |= 1 << (i % 64);
+    }
 
-            if state_history.contains_key(&rocks_tilted) {
-                cycle_detected = true;
-                j = *state_history.get(&rocks_tilted).unwrap();
+    /// Indices of the words holding a range of bits in a line, relative to the start of the line.
+    fn words_in(range: &Range<usize>) -> Range<usize> {
+        if range.is_empty() {
+            0..0
+        } else {
+            range.start / 64..range.end.div_ceil(64)
+        }
+    }
+
+    fn count(&self, line: usize, range: Range<usize>) -> usize {
+        let line = self.line(line);
+        Self::words_in(&range)
+            .map(|w| (line[w] & word_mask(w, &range)).count_ones() as usize)
+            .sum()
+    }
+
+    /// Gather the set bits in a range of a line together at its start or end.
+    fn pack(&mut self, line: usize, range: Range<usize>, to_start: bool) {
+        let n = self.count(line, range.clone());
+        if n == 0 || n == range.len() {
+            return;
+        }
+        let packed = if to_start {
+            range.start..range.start + n
+        } else {
+            range.end - n..range.end
+        };
+
+        let offset = line * self.words_per_line;
+        for w in Self::words_in(&range) {
+            let word = &mut self.words[offset + w];
+            *word = *word & !word_mask(w, &range) | word_mask(w, &packed);
+        }
+    }
+
+    /// Swap lines and bits, a 64 by 64 block at a time.
+    fn transpose(&self) -> Self {
+        let mut transposed = Self::new(self.len, self.n_lines);
+        for line_block in 0..self.n_lines.div_ceil(64) {
+            for word in 0..self.words_per_line {
+                let mut block = [0; 64];
+                for (k, b) in block.iter_mut().enumerate() {
+                    let line = line_block * 64 + k;
+                    if line < self.n_lines {
+                        *b = self.words[line * self.words_per_line + word];
+                    }
+                }
+                transpose_block(&mut block);
+                for (j, b) in block.into_iter().enumerate() {
+                    let line = word * 64 + j;
+                    if line < transposed.n_lines {
+                        transposed.words[line * transposed.words_per_line + line_block] = b;
+                    }
+                }
+            }
+        }
+        transposed
+    }
+}
+
+/// Runs of cells along each line that are free of cube-shaped rocks, which round rocks roll
+/// along until they stop at one end.
+fn free_segments(cubes: &Bitboard) -> Vec<(usize, Range<usize>)> {
+    let mut segments = vec![];
+    for line in 0..cubes.n_lines {
+        let mut start = 0;
+        for i in 0..=cubes.len {
+            if i == cubes.len || cubes.get(line, i) {
+                if i > start {
+                    segments.push((line, start..i));
+                }
+                start = i + 1;
+            }
+        }
+    }
+    segments
+}
+
+/// A schedule of tilts, written as a string of directions like `NNEW`. Commas and spaces between
+/// tilts are ignored. A tilt, or a group of them in brackets or quotes, can be repeated by
+/// following it with `x` and a count, so the puzzle's spin cycle is `(N,W,S,E)x1000000000`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TiltProgram {
+    Tilt(TiltDirection),
+    Sequence(Vec<TiltProgram>),
+    Repeat(Box<TiltProgram>, usize),
+}
+
+impl FromStr for TiltProgram {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_sequence(&mut s.chars().peekable(), None)
+    }
+}
+
+impl TiltProgram {
+    /// Parse tilts up to the `close` character, or the end of the string if there isn't one.
+    fn parse_sequence(chars: &mut Peekable<Chars>, close: Option<char>) -> Result<Self, String> {
+        let mut items = vec![];
+        loop {
+            let item = match chars.next() {
+                None if close.is_none() => break,
+                None => return Err(format!("Missing closing {}", close.unwrap())),
+                Some(c) if Some(c) == close => break,
+                Some(c) if c == ',' || c.is_whitespace() => continue,
+                Some('(') => Self::parse_sequence(chars, Some(')'))?,
+                Some('"') => Self::parse_sequence(chars, Some('"'))?,
+                Some(c) => Self::Tilt(TiltDirection::from_str(&c.to_string())?),
+            };
+
+            if chars.next_if_eq(&'x').is_some() {
+                let mut count = String::new();
+                while let Some(digit) = chars.next_if(|c| c.is_ascii_digit()) {
+                    count.push(digit);
+                }
+                let count = count
+                    .parse::<usize>()
+                    .map_err(|_| "Expected a repeat count after x".to_string())?;
+                items.push(Self::Repeat(Box::new(item), count));
             } else {
-                state_history.insert(rocks_tilted.clone(), i);
+                items.push(item);
+            }
+        }
+        Ok(Self::Sequence(items))
+    }
+
+    /// Run the tilts on the platform. Repeats skip ahead once the platform starts cycling, so
+    /// huge counts are fine.
+    pub fn apply(&self, platform: &mut Platform) {
+        match self {
+            Self::Tilt(direction) => platform.tilt(*direction),
+            Self::Sequence(items) => {
+                for item in items {
+                    item.apply(platform);
+                }
             }
+            Self::Repeat(item, count) => platform.repeat(*count, &mut |p| item.apply(p)),
         }
+    }
+}
 
-        let remaining_steps = (1000000000 - j) % (i - j);
+/// The platform, with the round rocks stored as a bitboard of rows. The cube-shaped rocks never
+/// move, so the runs of free cells between them are worked out up front for rows and columns.
+#[derive(Clone)]
+pub struct Platform {
+    height: usize,
+    width: usize,
+    round: Bitboard,
+    cubes: Bitboard,
+    row_segments: Vec<(usize, Range<usize>)>,
+    column_segments: Vec<(usize, Range<usize>)>,
+}
+
+impl FromStr for Platform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = s.lines().collect::<Vec<_>>();
+        let height = lines.len();
+        let width = lines.first().map_or(0, |l| l.len());
 
-        for _ in 0..remaining_steps {
-            rocks_tilted = spin_rocks(rocks_tilted);
+        let mut round = Bitboard::new(height, width);
+        let mut cubes = Bitboard::new(height, width);
+        for (r, line) in lines.iter().enumerate() {
+            if line.len() != width {
+                return Err("Platform rows are not all the same length".to_string());
+            }
+            for (c, ch) in line.chars().enumerate() {
+                match ch {
+                    'O' => round.set(r, c),
+                    '#' => cubes.set(r, c),
+                    '.' => (),
+                    _ => return Err(format!("Invalid character on platform: {ch}")),
+                }
+            }
         }
-    } else {
-        rocks_tilted = tilt_rocks(&rocks, &TiltDirection::North);
-    }
-
-    transpose(&rocks_tilted)
-        .iter()
-        .map(|column| {
-            column
-                .iter()
-                .enumerate()
-                .filter(|(_, r)| **r == 'O')
-                .map(|(i, _)| (n_rows - i) as i64)
-                .sum::<i64>()
+
+        Ok(Self {
+            height,
+            width,
+            round,
+            row_segments: free_segments(&cubes),
+            column_segments: free_segments(&cubes.transpose()),
+            cubes,
         })
-        .sum()
+    }
+}
+
+impl Platform {
+    /// Roll every round rock along each segment to the start or end of it.
+    fn roll(board: &mut Bitboard, segments: &[(usize, Range<usize>)], to_start: bool) {
+        for (line, segment) in segments {
+            board.pack(*line, segment.clone(), to_start);
+        }
+    }
+
+    pub fn tilt(&mut self, direction: TiltDirection) {
+        match direction {
+            TiltDirection::West => Self::roll(&mut self.round, &self.row_segments, true),
+            TiltDirection::East => Self::roll(&mut self.round, &self.row_segments, false),
+            TiltDirection::North | TiltDirection::South => {
+                let mut columns = self.round.transpose();
+                Self::roll(
+                    &mut columns,
+                    &self.column_segments,
+                    direction == TiltDirection::North,
+                );
+                self.round = columns.transpose();
+            }
+        }
+    }
+
+    pub fn spin(&mut self) {
+        for direction in SPIN_CYCLE {
+            self.tilt(direction);
+        }
+    }
+
+    /// A 64-bit fingerprint of where the round rocks are.
+    fn state_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.round.words.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Apply `step` to the platform `times` times, skipping ahead once the arrangement of rocks
+    /// repeats. Only a hash of each arrangement is kept, so a repeat is confirmed by stepping
+    /// through the cycle once before skipping.
+    pub fn repeat(&mut self, times: usize, step: &mut dyn FnMut(&mut Self)) {
+        let mut seen = HashMap::from([(self.state_hash(), 0)]);
+        let mut i = 0;
+        while i < times {
+            step(self);
+            i += 1;
+
+            let Some(&j) = seen.get(&self.state_hash()) else {
+                seen.insert(self.state_hash(), i);
+                continue;
+            };
+
+            let start = self.round.clone();
+            let cycle_length = i - j;
+            if cycle_length > times - i {
+                continue;
+            }
+            for _ in 0..cycle_length {
+                step(self);
+            }
+            i += cycle_length;
+            if self.round == start {
+                // We're back where we started, so skip whole cycles.
+                let remaining = (times - i) % cycle_length;
+                for _ in 0..remaining {
+                    step(self);
+                }
+                return;
+            }
+            seen.insert(self.state_hash(), i);
+        }
+    }
+
+    /// Spin the platform `spins` times.
+    pub fn spin_many(&mut self, spins: usize) {
+        self.repeat(spins, &mut Self::spin);
+    }
+
+    /// Total load on the support beams along one edge, where each rock weighs one more than the
+    /// number of rows or columns between it and the opposite edge.
+    pub fn load(&self, edge: TiltDirection) -> usize {
+        let weighted_sum = |board: &Bitboard, reversed: bool| {
+            (0..board.n_lines)
+                .map(|line| {
+                    let weight = if reversed {
+                        board.n_lines - line
+                    } else {
+                        line + 1
+                    };
+                    board.count(line, 0..board.len) * weight
+                })
+                .sum()
+        };
+
+        match edge {
+            TiltDirection::North => weighted_sum(&self.round, true),
+            TiltDirection::South => weighted_sum(&self.round, false),
+            TiltDirection::West => weighted_sum(&self.round.transpose(), true),
+            TiltDirection::East => weighted_sum(&self.round.transpose(), false),
+        }
+    }
 }
 
-pub fn run(input_path: PathBuf, part_two: bool) -> Result<i64, Box<dyn Error>> {
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for r in 0..self.height {
+            let row = (0..self.width)
+                .map(|c| match (self.round.get(r, c), self.cubes.get(r, c)) {
+                    (true, _) => 'O',
+                    (_, true) => '#',
+                    _ => '.',
+                })
+                .collect::<String>();
+            writeln!(f, "{row}")?;
+        }
+        Ok(())
+    }
+}
+
+fn compute_answer(puzzle_input: &str, part_two: bool) -> Result<usize, String> {
+    let mut platform = Platform::from_str(puzzle_input)?;
+
+    if part_two {
+        platform.spin_many(SPINS);
+    } else {
+        platform.tilt(TiltDirection::North);
+    }
+    Ok(platform.load(TiltDirection::North))
+}
+
+pub fn run(input_path: PathBuf, part_two: bool) -> Result<usize, Box<dyn Error>> {
     let puzzle_input = fs::read_to_string(input_path)?;
-    let answer = compute_answer(&puzzle_input, part_two);
+    let answer = compute_answer(&puzzle_input, part_two)?;
     println!("The answer is {answer}");
     Ok(answer)
 }
+
+/// Run a schedule of tilts, then measure the load on the given edge, optionally printing the
+/// platform at the end.
+pub fn run_tilts(
+    input_path: PathBuf,
+    program: &TiltProgram,
+    edge: TiltDirection,
+    print: bool,
+) -> Result<usize, Box<dyn Error>> {
+    let puzzle_input = fs::read_to_string(input_path)?;
+    let mut platform = Platform::from_str(&puzzle_input)?;
+    program.apply(&mut platform);
+    if print {
+        print!("{platform}");
+    }
+
+    let load = platform.load(edge);
+    println!("The load on the {edge:?} edge is {load}");
+    Ok(load)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transpose() {
+        // Awkward sizes so blocks overhang both edges.
+        let (n_lines, len) = (70, 130);
+        let mut board = Bitboard::new(n_lines, len);
+        for line in 0..n_lines {
+            for i in 0..len {
+                if (line * 7 + i * 13) % 5 == 0 {
+                    board.set(line, i);
+                }
+            }
+        }
+
+        let transposed = board.transpose();
+        for line in 0..n_lines {
+            for i in 0..len {
+                assert_eq!(board.get(line, i), transposed.get(i, line));
+            }
+        }
+        assert_eq!(transposed.transpose(), board);
+    }
+
+    #[test]
+    fn test_count_and_pack() {
+        let mut board = Bitboard::new(1, 200);
+        for i in (0..200).step_by(3) {
+            board.set(0, i);
+        }
+        assert_eq!(board.count(0, 0..200), 67);
+        assert_eq!(board.count(0, 50..70), 7);
+        assert_eq!(board.count(0, 128..128), 0);
+        assert_eq!(board.count(0, 130..130), 0);
+
+        board.pack(0, 10..150, true);
+        assert_eq!(board.count(0, 10..56), 46);
+        assert_eq!(board.count(0, 56..150), 0);
+        board.pack(0, 10..150, false);
+        assert_eq!(board.count(0, 10..104), 0);
+        assert_eq!(board.count(0, 104..150), 46);
+        assert_eq!(board.count(0, 0..200), 67);
+    }
+
+    /// Tilt a grid of characters by moving rocks one step at a time until none can move.
+    fn naive_tilt(grid: &mut [Vec<char>], (dr, dc): (isize, isize)) {
+        let (height, width) = (grid.len() as isize, grid[0].len() as isize);
+        let mut moved = true;
+        while moved {
+            moved = false;
+            for r in 0..height {
+                for c in 0..width {
+                    let (nr, nc) = (r + dr, c + dc);
+                    if (0..height).contains(&nr)
+                        && (0..width).contains(&nc)
+                        && grid[r as usize][c as usize] == 'O'
+                        && grid[nr as usize][nc as usize] == '.'
+                    {
+                        grid[r as usize][c as usize] = '.';
+                        grid[nr as usize][nc as usize] = 'O';
+                        moved = true;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_tilt_matches_naive() {
+        // Big enough to span several words in each direction.
+        let mut grid = (0..70)
+            .map(|r| {
+                (0..90)
+                    .map(|c| match (r * 31 + c * 17 + r * c) % 11 {
+                        0 | 1 => '#',
+                        2..=5 => 'O',
+                        _ => '.',
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let to_string = |grid: &[Vec<char>]| {
+            grid.iter()
+                .map(|row| row.iter().collect::<String>())
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        let mut platform = Platform::from_str(&to_string(&grid)).unwrap();
+
+        for _ in 0..3 {
+            for (direction, offset) in [
+                (TiltDirection::North, (-1, 0)),
+                (TiltDirection::West, (0, -1)),
+                (TiltDirection::South, (1, 0)),
+                (TiltDirection::East, (0, 1)),
+            ] {
+                platform.tilt(direction);
+                naive_tilt(&mut grid, offset);
+                for (r, row) in grid.iter().enumerate() {
+                    for (c, cell) in row.iter().enumerate() {
+                        assert_eq!(platform.round.get(r, c), *cell == 'O');
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_program() {
+        use TiltDirection::*;
+        use TiltProgram::*;
+
+        let program = TiltProgram::from_str("NNEW").unwrap();
+        assert_eq!(
+            program,
+            Sequence(vec![Tilt(North), Tilt(North), Tilt(East), Tilt(West)])
+        );
+
+        let program = TiltProgram::from_str("\"N,W,S,E\"x1000000000").unwrap();
+        let spin = Sequence(vec![Tilt(North), Tilt(West), Tilt(South), Tilt(East)]);
+        assert_eq!(program, Sequence(vec![Repeat(Box::new(spin), 1000000000)]));
+
+        let program = TiltProgram::from_str("S (E Wx2)x3").unwrap();
+        let group = Sequence(vec![Tilt(East), Repeat(Box::new(Tilt(West)), 2)]);
+        assert_eq!(
+            program,
+            Sequence(vec![Tilt(South), Repeat(Box::new(group), 3)])
+        );
+
+        assert!(TiltProgram::from_str("(NW").is_err());
+        assert!(TiltProgram::from_str("Nx").is_err());
+        assert!(TiltProgram::from_str("NQ").is_err());
+    }
+
+    #[test]
+    fn test_large_platform() {
+        // A wide platform with nothing to stop the rocks, so after a spin every row has its 100
+        // rocks packed into the right half.
+        let row = "O.".repeat(100);
+        let input = vec![row; 150].join("\n");
+        let mut platform = Platform::from_str(&input).unwrap();
+        platform.spin_many(SPINS);
+
+        let expected = (1..=150).sum::<usize>() * 100;
+        assert_eq!(platform.load(TiltDirection::North), expected);
+    }
+}
@@ -1,13 +1,39 @@
 use clap::Parser;
 use std::process;
 
-use day14::run;
+use day14::{run, run_tilts, TiltDirection, TiltProgram};
 use util::Cli;
 
+#[derive(Parser)]
+struct Day14Cli {
+    #[command(flatten)]
+    cli: Cli,
+
+    /// Tilt the platform following this schedule instead, e.g. NNEW. Groups of tilts in brackets
+    /// or quotes can be repeated with x, e.g. (N,W,S,E)x1000000000.
+    #[arg(long)]
+    tilts: Option<TiltProgram>,
+
+    /// Edge to measure the load on after the tilts: N, E, S or W.
+    #[arg(long, default_value = "N", requires = "tilts")]
+    load_edge: TiltDirection,
+
+    /// Print the platform after the tilts.
+    #[arg(long, requires = "tilts")]
+    print: bool,
+}
+
 fn main() {
-    let cli = Cli::parse();
+    let cli = Day14Cli::parse();
+    let path = cli.cli.puzzle_input_path;
+
+    let result = if let Some(program) = cli.tilts {
+        run_tilts(path, &program, cli.load_edge, cli.print).map(|_| ())
+    } else {
+        run(path, cli.cli.part_two).map(|_| ())
+    };
 
-    if let Err(e) = run(cli.puzzle_input_path, cli.part_two) {
+    if let Err(e) = result {
         eprintln!("Error: {e}");
         process::exit(1);
     }
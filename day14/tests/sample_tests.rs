@@ -15,3 +15,44 @@ fn test_part_two() {
     let answer = day14::run(input_path, true).unwrap();
     assert_eq!(answer, 64);
 }
+
+#[test]
+fn test_tilts() {
+    use day14::{TiltDirection, TiltProgram};
+    use std::str::FromStr;
+
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+
+    let program = TiltProgram::from_str("N").unwrap();
+    let answer = day14::run_tilts(input_path.clone(), &program, TiltDirection::North, false);
+    assert_eq!(answer.unwrap(), 136);
+
+    let program = TiltProgram::from_str("\"N,W,S,E\"x1000000000").unwrap();
+    let answer = day14::run_tilts(input_path, &program, TiltDirection::North, false);
+    assert_eq!(answer.unwrap(), 64);
+}
+
+#[test]
+fn test_print_after_one_cycle() {
+    use day14::{Platform, TiltProgram};
+    use std::{fs, str::FromStr};
+
+    let mut input_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    input_path.push("tests/data/sample_input.txt");
+    let mut platform = Platform::from_str(&fs::read_to_string(input_path).unwrap()).unwrap();
+    TiltProgram::from_str("NWSE").unwrap().apply(&mut platform);
+    let expected = "\
+.....#....
+....#...O#
+...OO##...
+.OO#......
+.....OOO#.
+.O#...O#.#
+....O#....
+......OOOO
+#...O###..
+#..OO#....
+";
+    assert_eq!(platform.to_string(), expected);
+}